@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+/// Flags that never take a value.
+const SWITCHES: &[&str] = &["solve", "hangs", "any-panic", "explain", "stream"];
+
+/// Command line split into positional arguments and `--flag value` pairs.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub positional: Vec<String>,
+    flags: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Args {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg);
+                continue;
+            };
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None if SWITCHES.contains(&flag) => (flag.to_string(), String::new()),
+                None => {
+                    let value = args
+                        .next()
+                        .unwrap_or_else(|| panic!("missing value for --{flag}"));
+                    (flag.to_string(), value)
+                }
+            };
+            parsed.flags.insert(name, value);
+        }
+
+        parsed
+    }
+
+    pub fn flag(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).map(String::as_str)
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    /// Parses the value of `--name`, panicking with the flag name if it is malformed.
+    pub fn parsed_flag<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.flag(name).map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid value for --{name}: {value}"))
+        })
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::{Config, Format},
+    days::{self, Implementation},
+    external::run_external,
+    load_input, solve_with_timeout,
+};
+
+/// One implementation's go at an input.
+struct Run {
+    name: String,
+    solution: Result<(String, String), String>,
+    stderr: String,
+    elapsed: Duration,
+}
+
+/// Every way we know of to solve `day` worth comparing: its own implementations bar the naive
+/// one, then the configured external solvers.
+pub fn implementations(day: u8, config: &Config) -> Vec<(String, Implementation)> {
+    let entry = days::get(day).expect("day not implemented");
+    let builtin = entry
+        .compared_implementations()
+        .into_iter()
+        .map(|(name, solve)| (name.to_string(), Implementation::Builtin(solve)));
+    let external = config
+        .external_solvers(day)
+        .into_iter()
+        .map(|(name, command)| (name, Implementation::External(command)));
+
+    builtin.chain(external).collect()
+}
+
+fn run(
+    name: String,
+    implementation: &Implementation,
+    input: &str,
+    timeout: Option<Duration>,
+) -> Run {
+    let start = Instant::now();
+    let (solution, stderr) = match implementation {
+        Implementation::Builtin(solve) => {
+            let (solve, input) = (*solve, input.to_string());
+            // one implementation falling over shouldn't take the rest of the comparison with it
+            let solution = panic::catch_unwind(AssertUnwindSafe(|| {
+                solve_with_timeout(move || solve(&input), timeout)
+            }))
+            .map_err(|payload| format!("panicked: {}", panic_message(&*payload)))
+            .and_then(|solution| {
+                solution.ok_or_else(|| format!("timed out after {:?}", timeout.unwrap()))
+            });
+            (solution, String::new())
+        }
+        Implementation::External(command) => {
+            let external = run_external(command, input, timeout);
+            (external.solution, external.stderr)
+        }
+    };
+
+    Run {
+        name,
+        solution,
+        stderr,
+        elapsed: start.elapsed(),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("no message")
+}
+
+/// Runs every implementation of a day on the same input, timing each and checking that they
+/// all give the default one's answers.
+pub fn compare_implementations(day: u8, input: &str, config: &Config, with_header: bool) {
+    let timeout = config.day(day).timeout();
+    let runs: Vec<_> = implementations(day, config)
+        .into_iter()
+        .map(|(name, implementation)| run(name, &implementation, input, timeout))
+        .collect();
+    let expected = &runs[0].solution;
+    let agree = expected.is_ok() && runs.iter().all(|run| run.solution == *expected);
+
+    match config.format {
+        Format::Text => {
+            if with_header {
+                println!("Day {day}");
+            }
+            let width = runs.iter().map(|run| run.name.len()).max().unwrap();
+            for run in &runs {
+                let answers = match &run.solution {
+                    Ok((p1, p2)) => format!("{p1}, {p2}"),
+                    Err(reason) => reason.clone(),
+                };
+                let mark = if run.solution == *expected {
+                    ""
+                } else {
+                    "  <- differs"
+                };
+                println!(
+                    "{:width$}  {:>12}  {answers}{mark}",
+                    run.name,
+                    format!("{:.2?}", run.elapsed)
+                );
+            }
+            if agree {
+                println!("all {} implementations agree", runs.len());
+            } else {
+                println!("implementations disagree");
+                print_stderr(&runs, expected);
+            }
+        }
+        Format::Json => {
+            let implementations: Vec<_> = runs
+                .iter()
+                .map(|run| {
+                    serde_json::json!({
+                        "name": run.name,
+                        "part1": run.solution.as_ref().ok().map(|(p1, _)| p1),
+                        "part2": run.solution.as_ref().ok().map(|(_, p2)| p2),
+                        "error": run.solution.as_ref().err(),
+                        "stderr": run.stderr,
+                        "ns": run.elapsed.as_nanos() as u64,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::json!({ "day": day, "implementations": implementations, "agree": agree })
+            );
+        }
+    }
+}
+
+/// Checks every configured external solver against our default solver on the real input of
+/// each day that has one, or just `only_day`.
+pub fn verify(only_day: Option<u8>, config: &Config) {
+    let mut verified = 0;
+    for (day, entry) in days::all() {
+        if only_day.is_some_and(|only_day| only_day != day) {
+            continue;
+        }
+        let external = config.external_solvers(day);
+        if external.is_empty() {
+            continue;
+        }
+        verified += 1;
+        let Ok(input) = load_input(day, config) else {
+            println!("day {day}: no input to verify against");
+            continue;
+        };
+
+        let timeout = config.day(day).timeout();
+        let expected = run(
+            "default".to_string(),
+            &Implementation::Builtin(entry.solve),
+            &input,
+            timeout,
+        )
+        .solution;
+        let Ok((e1, e2)) = &expected else {
+            println!("day {day}: our own solver {}", expected.unwrap_err());
+            continue;
+        };
+        let runs: Vec<_> = external
+            .into_iter()
+            .map(|(name, command)| run(name, &Implementation::External(command), &input, timeout))
+            .collect();
+
+        for run in &runs {
+            match &run.solution {
+                Ok(_) if run.solution == expected => {
+                    println!("day {day}: {} agrees ({:.2?})", run.name, run.elapsed)
+                }
+                Ok((p1, p2)) => {
+                    println!(
+                        "day {day}: {} says {p1}, {p2} but we say {e1}, {e2}",
+                        run.name
+                    )
+                }
+                Err(reason) => println!("day {day}: {} {reason}", run.name),
+            }
+        }
+        print_stderr(&runs, &expected);
+    }
+
+    if verified == 0 {
+        println!("no external solvers to verify, add some under [external] in aoc.toml");
+    }
+}
+
+/// Shows what the runs that went wrong had to say for themselves.
+fn print_stderr(runs: &[Run], expected: &Result<(String, String), String>) {
+    for run in runs {
+        if run.solution != *expected && !run.stderr.trim().is_empty() {
+            println!("{} stderr:", run.name);
+            println!("  {}", run.stderr.trim_end().replace('\n', "\n  "));
+        }
+    }
+}
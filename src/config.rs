@@ -0,0 +1,326 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::Args;
+
+const CONFIG_FILE_NAME: &str = "aoc.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format {s}")),
+        }
+    }
+}
+
+/// Settings that apply to a single day once every layer has been resolved.
+#[derive(Debug, Clone, Serialize)]
+pub struct DayConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skip: bool,
+    /// External solvers for just this day, on top of the global ones.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub external: BTreeMap<String, Vec<String>>,
+}
+
+impl DayConfig {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct DayLayer {
+    timeout: Option<u64>,
+    skip: Option<bool>,
+    external: BTreeMap<String, Vec<String>>,
+}
+
+/// One source of settings (CLI, environment or file). Anything left unset falls
+/// through to the next source down.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct Layer {
+    input_dir: Option<PathBuf>,
+    year: Option<u16>,
+    threads: Option<usize>,
+    bench_iterations: Option<u32>,
+    format: Option<Format>,
+    timeout: Option<u64>,
+    skip: Option<Vec<u8>>,
+    external: BTreeMap<String, Vec<String>>,
+    day: BTreeMap<String, DayLayer>,
+}
+
+impl Layer {
+    fn from_args(args: &Args) -> Layer {
+        Layer {
+            input_dir: args.parsed_flag("input-dir"),
+            year: args.parsed_flag("year"),
+            threads: args.parsed_flag("threads"),
+            bench_iterations: args.parsed_flag("bench-iterations"),
+            format: args.parsed_flag("format"),
+            timeout: args.parsed_flag("timeout"),
+            skip: args.flag("skip").map(|list| parse_day_list(list, "--skip")),
+            external: BTreeMap::new(),
+            day: BTreeMap::new(),
+        }
+    }
+
+    fn from_env() -> Layer {
+        Layer {
+            input_dir: env_var("AOC_INPUT_DIR"),
+            year: env_var("AOC_YEAR"),
+            threads: env_var("AOC_THREADS"),
+            bench_iterations: env_var("AOC_BENCH_ITERATIONS"),
+            format: env_var("AOC_FORMAT"),
+            timeout: env_var("AOC_TIMEOUT"),
+            skip: env::var("AOC_SKIP")
+                .ok()
+                .map(|list| parse_day_list(&list, "AOC_SKIP")),
+            external: BTreeMap::new(),
+            day: BTreeMap::new(),
+        }
+    }
+
+    fn from_file(path: &Path) -> Layer {
+        let contents = read_to_string(path)
+            .unwrap_or_else(|e| panic!("could not read {}: {e}", path.display()));
+        let mut layer: Layer = toml::from_str(&contents)
+            .unwrap_or_else(|e| panic!("invalid config {}: {e}", path.display()));
+
+        // relative paths in the file are relative to the file, not to wherever we were run from
+        if let (Some(input_dir), Some(parent)) = (&layer.input_dir, path.parent()) {
+            layer.input_dir = Some(parent.join(input_dir));
+        }
+        for key in layer.day.keys() {
+            key.parse::<u8>()
+                .unwrap_or_else(|_| panic!("invalid day [day.{key}] in {}", path.display()));
+        }
+        let day_externals = layer.day.values().flat_map(|day| &day.external);
+        for (name, command) in layer.external.iter().chain(day_externals) {
+            if command.is_empty() {
+                panic!(
+                    "external solver {name} in {} has no command",
+                    path.display()
+                );
+            }
+        }
+
+        layer
+    }
+
+    fn day_timeout(&self, day: u8) -> Option<u64> {
+        self.day
+            .get(&day.to_string())
+            .and_then(|d| d.timeout)
+            .or(self.timeout)
+    }
+
+    fn day_skip(&self, day: u8) -> Option<bool> {
+        self.day
+            .get(&day.to_string())
+            .and_then(|d| d.skip)
+            .or_else(|| self.skip.as_ref()?.contains(&day).then_some(true))
+    }
+}
+
+/// Fully resolved settings. Precedence is CLI > environment > `aoc.toml` > defaults.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// A specific input file from `--input`, used in place of the day's usual one.
+    #[serde(skip)]
+    pub input: Option<PathBuf>,
+    /// Whether `--explain` asked solvers to show their working.
+    #[serde(skip)]
+    pub explain: bool,
+    /// Whether `--stream` asked for the input to be solved a line at a time as it is read.
+    #[serde(skip)]
+    pub stream: bool,
+    /// Which of a day's implementations `--impl` asked for, or "all" to compare them.
+    #[serde(skip)]
+    pub implementation: Option<String>,
+    pub input_dir: PathBuf,
+    pub year: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads: Option<usize>,
+    pub bench_iterations: u32,
+    pub format: Format,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+    /// Other people's solvers for every day, by name, with `{day}` in their commands standing
+    /// for the day number.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    external: BTreeMap<String, Vec<String>>,
+    /// Only days whose settings differ from the global ones.
+    #[serde(rename = "day")]
+    days: BTreeMap<u8, DayConfig>,
+}
+
+impl Config {
+    pub fn load(args: &Args) -> Config {
+        let path = args
+            .flag("config")
+            .map(PathBuf::from)
+            .or_else(|| env::var("AOC_CONFIG").ok().map(PathBuf::from))
+            .or_else(find_config_file);
+
+        let layers = [
+            Layer::from_args(args),
+            Layer::from_env(),
+            path.as_deref().map(Layer::from_file).unwrap_or_default(),
+        ];
+
+        let timeout = layers.iter().find_map(|layer| layer.timeout);
+        let days = (1..=25)
+            .map(|day| {
+                let timeout = layers.iter().find_map(|layer| layer.day_timeout(day));
+                let skip = layers
+                    .iter()
+                    .find_map(|layer| layer.day_skip(day))
+                    .unwrap_or(false);
+                let external = layers
+                    .iter()
+                    .rev()
+                    .filter_map(|layer| layer.day.get(&day.to_string()))
+                    .flat_map(|day| day.external.clone())
+                    .collect();
+                (
+                    day,
+                    DayConfig {
+                        timeout,
+                        skip,
+                        external,
+                    },
+                )
+            })
+            .filter(|(_, day_config)| {
+                day_config.timeout != timeout || day_config.skip || !day_config.external.is_empty()
+            })
+            .collect();
+        // lower layers go in first so higher ones replace solvers of the same name
+        let external = layers
+            .iter()
+            .rev()
+            .flat_map(|layer| layer.external.clone())
+            .collect();
+
+        Config {
+            path,
+            input: args.flag("input").map(PathBuf::from),
+            explain: args.switch("explain"),
+            stream: args.switch("stream"),
+            implementation: args.flag("impl").map(str::to_string),
+            input_dir: layers
+                .iter()
+                .find_map(|layer| layer.input_dir.clone())
+                .unwrap_or_else(|| PathBuf::from("inputs")),
+            year: layers.iter().find_map(|layer| layer.year).unwrap_or(2023),
+            threads: layers.iter().find_map(|layer| layer.threads),
+            bench_iterations: layers
+                .iter()
+                .find_map(|layer| layer.bench_iterations)
+                .unwrap_or(10),
+            format: layers
+                .iter()
+                .find_map(|layer| layer.format)
+                .unwrap_or_default(),
+            timeout,
+            external,
+            days,
+        }
+    }
+
+    pub fn day(&self, day: u8) -> DayConfig {
+        self.days.get(&day).cloned().unwrap_or(DayConfig {
+            timeout: self.timeout,
+            skip: false,
+            external: BTreeMap::new(),
+        })
+    }
+
+    /// The external solvers to run for `day`, by name, with their commands filled in.
+    pub fn external_solvers(&self, day: u8) -> BTreeMap<String, Vec<String>> {
+        let global = self.external.iter().map(|(name, command)| {
+            let command = command
+                .iter()
+                .map(|arg| arg.replace("{day}", &day.to_string()))
+                .collect();
+            (name.clone(), command)
+        });
+
+        global.chain(self.day(day).external).collect()
+    }
+
+    pub fn input_path(&self, day: u8) -> PathBuf {
+        if let Some(input) = &self.input {
+            return input.clone();
+        }
+        self.input_dir.join(format!("input{day}.txt"))
+    }
+
+    /// Where the puzzle site serves the day's input for `year`.
+    pub fn input_url(&self, day: u8) -> String {
+        format!("https://adventofcode.com/{}/day/{day}/input", self.year)
+    }
+
+    pub fn show(&self) -> String {
+        let source = match &self.path {
+            Some(path) => format!("# loaded from {}\n", path.display()),
+            None => format!("# no {CONFIG_FILE_NAME} found, using defaults\n"),
+        };
+
+        source + &toml::to_string(self).expect("config should serialize")
+    }
+}
+
+fn find_config_file() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn env_var<T: FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid value for {name}: {value}"))
+    })
+}
+
+fn parse_day_list(list: &str, source: &str) -> Vec<u8> {
+    list.split(',')
+        .map(|day| {
+            day.trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid day in {source}: {day}"))
+        })
+        .collect()
+}
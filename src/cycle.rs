@@ -0,0 +1,146 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// A simulation that's been run until a state came round again: from `start` steps on, the
+/// states repeat every `length` steps.
+pub struct Cycle<S, F> {
+    pub start: usize,
+    pub length: usize,
+    initial: S,
+    /// The state after `start` steps, the first one that comes back.
+    repeated: S,
+    step: F,
+}
+
+/// Runs `step` from `initial` until it gets back to a state it's seen, remembering every state
+/// it passes through to spot that.
+pub fn find<S: Clone + Hash + Eq, F: Fn(&S) -> S>(initial: S, step: F) -> Cycle<S, F> {
+    find_by(initial, step, S::clone)
+}
+
+/// The same as `find`, but only remembering each state's `fingerprint`, which can be much
+/// smaller than the state. Two states are allowed to share a fingerprint: when one comes up
+/// again, the earlier state is worked out afresh from `initial` to check it really is the same.
+pub fn find_by<S: Clone + Eq, K: Hash + Eq, F: Fn(&S) -> S>(
+    initial: S,
+    step: F,
+    fingerprint: impl Fn(&S) -> K,
+) -> Cycle<S, F> {
+    // the steps after which each fingerprint has been seen
+    let mut seen: HashMap<K, Vec<usize>> = HashMap::new();
+    let mut state = initial.clone();
+    for steps in 0.. {
+        let earlier = seen.entry(fingerprint(&state)).or_default();
+        let start = earlier.iter().copied().find(|&start| {
+            let replayed = (0..start).fold(initial.clone(), |state, _| step(&state));
+            replayed == state
+        });
+        if let Some(start) = start {
+            return Cycle {
+                start,
+                length: steps - start,
+                initial,
+                repeated: state,
+                step,
+            };
+        }
+        earlier.push(steps);
+        state = step(&state);
+    }
+
+    unreachable!()
+}
+
+impl<S: Clone, F: Fn(&S) -> S> Cycle<S, F> {
+    /// The state after `steps` steps from the initial one, which takes fewer than `start` or
+    /// `length` more steps to work out.
+    pub fn state_after(&self, steps: usize) -> S {
+        let (mut state, remaining) = if steps < self.start {
+            (self.initial.clone(), steps)
+        } else {
+            (self.repeated.clone(), (steps - self.start) % self.length)
+        };
+        for _ in 0..remaining {
+            state = (self.step)(&state);
+        }
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{self, Rng};
+
+    const CASES: u64 = 1000;
+
+    #[test]
+    fn cycle_holds() {
+        rng::check_cases(CASES, check_cycle);
+    }
+
+    #[test]
+    fn fixed_point_from_the_start() {
+        let cycle = find(7, |&n| n);
+        assert_eq!((cycle.start, cycle.length), (0, 1));
+        assert_eq!(cycle.state_after(0), 7);
+        assert_eq!(cycle.state_after(1_000_000_000), 7);
+    }
+
+    #[test]
+    fn fixed_point_after_a_lead_in() {
+        // counts down to zero and stays there
+        let cycle = find(3u32, |&n| n.saturating_sub(1));
+        assert_eq!((cycle.start, cycle.length), (3, 1));
+        assert_eq!(cycle.state_after(2), 1);
+        assert_eq!(cycle.state_after(usize::MAX), 0);
+    }
+
+    #[test]
+    fn every_fingerprint_the_same() {
+        let cycle = find_by(0, |&n| (n + 1) % 4, |_| ());
+        assert_eq!((cycle.start, cycle.length), (0, 4));
+        assert_eq!(cycle.state_after(10), 2);
+    }
+
+    /// Follows a random map from `0..n` to itself, where every start runs into a cycle.
+    fn check_cycle(rng: &mut Rng) -> Result<(), String> {
+        let n = rng.range(1..50) as usize;
+        let map: Vec<usize> = (0..n).map(|_| rng.below(n)).collect();
+        let initial = rng.below(n);
+
+        let mut path = vec![initial];
+        let (start, length) = loop {
+            let next = map[*path.last().unwrap()];
+            if let Some(start) = path.iter().position(|&state| state == next) {
+                break (start, path.len() - start);
+            }
+            path.push(next);
+        };
+        let expected_after = |steps: usize| (0..steps).fold(initial, |state, _| map[state]);
+
+        let step = |&state: &usize| map[state];
+        let by_state = find(initial, step);
+        // a fingerprint that lumps states together, which has to be seen through
+        let by_fingerprint = find_by(initial, step, |&state| state % 3);
+        for cycle in [&by_state, &by_fingerprint] {
+            if (cycle.start, cycle.length) != (start, length) {
+                return Err(format!(
+                    "{map:?} from {initial} starts a cycle of {} at {}, not {length} at {start}",
+                    cycle.length, cycle.start
+                ));
+            }
+            for steps in [0, start, start + length, rng.below(1000)] {
+                if cycle.state_after(steps) != expected_after(steps) {
+                    return Err(format!(
+                        "{map:?} from {initial} is at {} after {steps} steps, not {}",
+                        cycle.state_after(steps),
+                        expected_after(steps)
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,21 +1,25 @@
-use std::collections::HashMap;
-use std::fs::read_to_string;
+use std::{collections::HashMap, io::BufRead};
 
-pub fn solve() -> (String, String) {
-    let input = read_to_string("inputs/input1.txt").expect("input not readable");
+use crate::rng::Rng;
 
-    let p1: u32 = input
-        .lines()
-        .map(|line| {
-            let digits: Vec<_> = line.chars().filter(|ch| ch.is_ascii_digit()).collect();
-            let calibration_value =
-                digits.first().unwrap().to_string() + &digits.last().unwrap().to_string();
+pub fn solve(input: &str) -> (String, String) {
+    let digit_words = digit_words();
+    let (p1, p2) = super::sum_lines(input, |line| line_values(line, &digit_words));
 
-            calibration_value.parse::<u32>().unwrap()
-        })
-        .sum();
+    (p1.to_string(), p2.to_string())
+}
+
+pub fn solve_stream(input: &mut dyn BufRead) -> (String, String) {
+    let digit_words = digit_words();
+    let (p1, p2) = super::sum_line_iter(super::stream_lines(input), |line| {
+        line_values(line, &digit_words)
+    });
+
+    (p1.to_string(), p2.to_string())
+}
 
-    let digit_words = HashMap::from([
+fn digit_words() -> HashMap<&'static str, &'static str> {
+    HashMap::from([
         ("zero", "0"),
         ("one", "1"),
         ("two", "2"),
@@ -26,29 +30,85 @@ pub fn solve() -> (String, String) {
         ("seven", "7"),
         ("eight", "8"),
         ("nine", "9"),
-    ]);
-
-    let p2: u32 = input
-        .lines()
-        .map(|line| {
-            let mut digits: Vec<String> = vec![];
-            for (i, ch) in line.char_indices() {
-                if ch.is_ascii_digit() {
-                    digits.push(ch.to_string());
-                } else {
-                    for k in digit_words.keys() {
-                        if line[i..].starts_with(k) {
-                            digits.push(digit_words.get(k).unwrap().to_string());
-                        }
-                    }
+    ])
+}
+
+fn line_values(line: &str, digit_words: &HashMap<&str, &str>) -> (u32, u32) {
+    (
+        calibration_value(line),
+        spelled_calibration_value(line, digit_words),
+    )
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    serde_json::json!(input.lines().collect::<Vec<_>>())
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    if !input.chars().any(|ch| ch.is_ascii_alphabetic()) {
+        return 0;
+    }
+
+    input
+        .split_inclusive('\n')
+        .take_while(|line| {
+            let line = line.trim_end();
+            line.chars().all(|ch| ch.is_ascii_alphanumeric())
+                && line.chars().any(|ch| ch.is_ascii_digit())
+        })
+        .map(str::len)
+        .sum()
+}
+
+/// The first and last real digits of `line`, as a two digit number.
+fn calibration_value(line: &str) -> u32 {
+    let digits: Vec<_> = line.chars().filter(|ch| ch.is_ascii_digit()).collect();
+    let calibration_value =
+        digits.first().unwrap().to_string() + &digits.last().unwrap().to_string();
+
+    calibration_value.parse::<u32>().unwrap()
+}
+
+fn spelled_calibration_value(line: &str, digit_words: &HashMap<&str, &str>) -> u32 {
+    let mut digits: Vec<String> = vec![];
+    for (i, ch) in line.char_indices() {
+        if ch.is_ascii_digit() {
+            digits.push(ch.to_string());
+        } else {
+            for k in digit_words.keys() {
+                if line[i..].starts_with(k) {
+                    digits.push(digit_words.get(k).unwrap().to_string());
                 }
             }
-            let calibration_value =
-                digits.first().unwrap().to_string() + &digits.last().unwrap().to_string();
+        }
+    }
+    let calibration_value =
+        digits.first().unwrap().to_string() + &digits.last().unwrap().to_string();
 
-            calibration_value.parse::<u32>().unwrap()
+    calibration_value.parse::<u32>().unwrap()
+}
+
+/// Lines of letters, digits and spelled out digits, each with at least one real digit.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let words = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    let lines: Vec<String> = (0..10 * size)
+        .map(|_| {
+            let mut pieces: Vec<String> = (0..rng.below(6))
+                .map(|_| match rng.below(3) {
+                    0 => rng.choose(&words).to_string(),
+                    1 => rng.range(1..10).to_string(),
+                    _ => (0..rng.range(1..4))
+                        .map(|_| (b'a' + rng.below(26) as u8) as char)
+                        .collect(),
+                })
+                .collect();
+            let digit = rng.range(1..10).to_string();
+            pieces.insert(rng.below(pieces.len() + 1), digit);
+            pieces.concat()
         })
-        .sum();
+        .collect();
 
-    (p1.to_string(), p2.to_string())
+    lines.join("\n")
 }
@@ -1,12 +1,17 @@
-use std::{collections::HashMap, fs::read_to_string};
+use std::collections::HashMap;
 
 use glam::{IVec2, ivec2};
 use itertools::Itertools;
-use pathfinding::prelude::dijkstra_reach;
+use serde::Serialize;
 
-type PipeDiagram = HashMap<IVec2, PipeType>;
+use crate::{
+    grid::{Grid, SparseGrid, Storage},
+    polygon::Polygon,
+    rng::Rng,
+    search::{self, SearchState},
+};
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize)]
 enum PipeType {
     NorthSouth,
     EastWest,
@@ -27,42 +32,155 @@ impl PipeType {
             PipeType::SouthEast => vec![ivec2(0, 1), ivec2(1, 0)],
         }
     }
+
+    fn from_char(ch: char) -> Option<PipeType> {
+        match ch {
+            '|' => Some(PipeType::NorthSouth),
+            '-' => Some(PipeType::EastWest),
+            'L' => Some(PipeType::NorthEast),
+            'J' => Some(PipeType::NorthWest),
+            '7' => Some(PipeType::SouthWest),
+            'F' => Some(PipeType::SouthEast),
+            _ => None,
+        }
+    }
+
+    fn from_directions(a: IVec2, b: IVec2) -> Option<PipeType> {
+        [
+            PipeType::NorthSouth,
+            PipeType::EastWest,
+            PipeType::NorthEast,
+            PipeType::NorthWest,
+            PipeType::SouthWest,
+            PipeType::SouthEast,
+        ]
+        .into_iter()
+        .find(|pipe_type| {
+            let directions = pipe_type.to_directions();
+            directions.contains(&a) && directions.contains(&b) && a != b
+        })
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            PipeType::NorthSouth => '|',
+            PipeType::EastWest => '-',
+            PipeType::NorthEast => 'L',
+            PipeType::NorthWest => 'J',
+            PipeType::SouthWest => '7',
+            PipeType::SouthEast => 'F',
+        }
+    }
 }
 
+/// A tile to follow the pipes on from. There's no goal, the search just runs out at the far
+/// side of the loop.
+impl<S: Storage<Option<PipeType>>> SearchState<S> for IVec2 {
+    fn successors(&self, pipe_diagram: &S) -> Vec<(IVec2, u64)> {
+        match pipe_diagram.get(*self) {
+            Some(Some(pipe_type)) => pipe_type
+                .to_directions()
+                .iter()
+                .map(|direction| (self + direction, 1))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+const ORTHOGONAL: [IVec2; 4] = [ivec2(0, -1), ivec2(1, 0), ivec2(0, 1), ivec2(-1, 0)];
+
 const _EX: &str = r"..F7.
 .FJ|.
 SJ.L7
 |F--J
 LJ...";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input10.txt").expect("could not read input");
-
-    let mut starting_point: IVec2 = ivec2(0, 0);
-    let mut pipe_diagram: PipeDiagram = HashMap::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, ch) in line.chars().enumerate() {
-            if ch == 'S' {
-                starting_point = ivec2(x as i32, y as i32);
-                continue;
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
+
+    let (pipe_diagram, starting_point) = parse_pipes(input);
+
+    solve_pipes(&pipe_diagram, starting_point)
+}
+
+/// Same answers as `solve` with the pipes in a hash map instead, to bench the dense grid against.
+pub fn solve_sparse(input: &str) -> (String, String) {
+    let (pipe_diagram, starting_point) = parse_pipes(input);
+
+    solve_pipes(&pipe_diagram.to_sparse(), starting_point)
+}
+
+fn solve_pipes(
+    pipe_diagram: &impl Storage<Option<PipeType>>,
+    starting_point: IVec2,
+) -> (String, String) {
+    let p1 = farthest_steps(pipe_diagram, starting_point);
+
+    let main_loop = walk_loop(starting_point, |pos| {
+        pipe_diagram.get(pos).unwrap().unwrap().to_directions()
+    });
+    let p2 = loop_polygon(&main_loop).interior_points();
+
+    (p1.to_string(), p2.to_string())
+}
+
+/// Same answers as `solve`, casting a ray east from every tile off the loop and counting
+/// the loop pipes it crosses instead of using Pick's theorem.
+pub fn solve_raycast(input: &str) -> (String, String) {
+    let (pipe_diagram, starting_point) = parse_pipes(input);
+    let p1 = farthest_steps(&pipe_diagram, starting_point);
+
+    let main_loop: SparseGrid<PipeType> = walk_loop(starting_point, |pos| {
+        pipe_diagram[pos].unwrap().to_directions()
+    })
+    .into_iter()
+    .map(|pos| (pos, pipe_diagram[pos].unwrap()))
+    .collect();
+    let IVec2 {
+        x: width,
+        y: height,
+    } = pipe_diagram.size();
+    let p2 = (1..(width - 1))
+        .cartesian_product(1..(height - 1))
+        .map(|(x, y)| ivec2(x, y))
+        .filter(|pos| !main_loop.contains(*pos))
+        .filter(|pos| {
+            let mut intersect_count = 0i32;
+            // traverse from the node to the east, checking for intersections by the even-odd rule
+            for x in (pos.x + 1)..width {
+                let check_pos = pos.with_x(x);
+                if let Some(pipe_type) = main_loop.get(check_pos) {
+                    // take the position of the intersection to be the upper part of the node
+                    if [
+                        PipeType::NorthSouth,
+                        PipeType::NorthWest,
+                        PipeType::NorthEast,
+                    ]
+                    .contains(pipe_type)
+                    {
+                        intersect_count += 1;
+                    }
+                }
             }
-            let pipe_type = match ch {
-                '|' => PipeType::NorthSouth,
-                '-' => PipeType::EastWest,
-                'L' => PipeType::NorthEast,
-                'J' => PipeType::NorthWest,
-                '7' => PipeType::SouthWest,
-                'F' => PipeType::SouthEast,
-                _ => continue,
-            };
-            pipe_diagram.insert(ivec2(x as i32, y as i32), pipe_type);
-        }
-    }
+            intersect_count % 2 == 1
+        })
+        .count();
+
+    (p1.to_string(), p2.to_string())
+}
+
+/// The pipes, with the start's own pipe worked out from which of its neighbours connect to
+/// it, and where the start is.
+fn parse_pipes(input: &str) -> (Grid<Option<PipeType>>, IVec2) {
+    let tiles = Grid::parse(input, |ch| ch);
+    let starting_point = tiles.find_all(|ch| *ch == 'S')[0];
+    let mut pipe_diagram = tiles.map(|ch| PipeType::from_char(*ch));
     // order: north, east, south, west
     let adjacent_pipes = (
         pipe_diagram
-            .get(&(starting_point + ivec2(0, -1)))
+            .get(starting_point + ivec2(0, -1))
+            .and_then(Option::as_ref)
             .map(|pipe_type| {
                 [
                     PipeType::NorthSouth,
@@ -73,13 +191,15 @@ pub fn solve() -> (String, String) {
             })
             .unwrap_or(false),
         pipe_diagram
-            .get(&(starting_point + ivec2(1, 0)))
+            .get(starting_point + ivec2(1, 0))
+            .and_then(Option::as_ref)
             .map(|pipe_type| {
                 [PipeType::EastWest, PipeType::NorthWest, PipeType::SouthWest].contains(pipe_type)
             })
             .unwrap_or(false),
         pipe_diagram
-            .get(&(starting_point + ivec2(0, 1)))
+            .get(starting_point + ivec2(0, 1))
+            .and_then(Option::as_ref)
             .map(|pipe_type| {
                 [
                     PipeType::NorthEast,
@@ -90,13 +210,13 @@ pub fn solve() -> (String, String) {
             })
             .unwrap_or(false),
         pipe_diagram
-            .get(&(starting_point + ivec2(-1, 0)))
+            .get(starting_point + ivec2(-1, 0))
+            .and_then(Option::as_ref)
             .map(|pipe_type| {
                 [PipeType::EastWest, PipeType::SouthEast, PipeType::NorthEast].contains(pipe_type)
             })
             .unwrap_or(false),
     );
-    dbg!(&adjacent_pipes);
     let starting_pipe = match adjacent_pipes {
         (true, false, true, false) => PipeType::NorthSouth,
         (true, true, false, false) => PipeType::NorthEast,
@@ -106,56 +226,199 @@ pub fn solve() -> (String, String) {
         (false, false, true, true) => PipeType::SouthWest,
         _ => unreachable!(),
     };
-    pipe_diagram.insert(starting_point, starting_pipe);
-    let reached_nodes: Vec<_> = dijkstra_reach(&starting_point, |pos| {
-        if let Some(pipe_type) = pipe_diagram.get(pos) {
-            pipe_type
-                .to_directions()
-                .iter()
-                .map(|direction| (direction + pos, 1))
+    pipe_diagram[starting_point] = Some(starting_pipe);
+
+    (pipe_diagram, starting_point)
+}
+
+/// How many steps along the loop the tile farthest from the start is.
+fn farthest_steps(pipe_diagram: &impl Storage<Option<PipeType>>, starting_point: IVec2) -> u64 {
+    search::bfs([starting_point], pipe_diagram)
+        .costs()
+        .map(|(_, steps)| steps)
+        .max()
+        .unwrap()
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let tiles = Grid::parse(input, |ch| ch);
+    let start = tiles.find_all(|ch| *ch == 'S');
+    let pipes = tiles.map(|ch| PipeType::from_char(*ch));
+
+    serde_json::json!({ "start": start.first(), "pipes": pipes })
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    if input.matches('S').count() != 1 {
+        return 0;
+    }
+
+    super::grid_len(input, |ch| "|-LJ7F.S".contains(ch))
+}
+
+/// Deliberately naive reference for differential testing: walks the loop tile by tile,
+/// then blows every tile up to 3x3 pixels and flood fills the outside instead of casting
+/// rays.
+pub fn solve_naive(input: &str) -> (String, String) {
+    let tiles = Grid::parse(input, |ch| ch);
+    let openings = |pos: IVec2| -> Vec<IVec2> {
+        tiles
+            .get(pos)
+            .and_then(|ch| PipeType::from_char(*ch))
+            .map(PipeType::to_directions)
+            .unwrap_or_default()
+    };
+
+    let start = tiles.find_all(|ch| *ch == 'S')[0];
+    let start_openings: Vec<_> = ORTHOGONAL
+        .into_iter()
+        .filter(|dir| openings(start + dir).contains(&-dir))
+        .collect();
+
+    let mut main_loop = vec![start];
+    let mut previous = start;
+    let mut current = start + start_openings[0];
+    while current != start {
+        main_loop.push(current);
+        let next = openings(current)
+            .into_iter()
+            .map(|dir| current + dir)
+            .find(|next| *next != previous)
+            .unwrap();
+        previous = current;
+        current = next;
+    }
+    let p1 = main_loop.len() / 2;
+
+    // pixel (3x + 1, 3y + 1) is the centre of tile (x, y); there's a one pixel gap all
+    // around so the flood fill can get between the map edge and the loop
+    let size = tiles.size() * 3 + ivec2(2, 2);
+    let mut walls = Grid::filled(size, false);
+    for &pos in &main_loop {
+        let centre = pos * 3 + ivec2(2, 2);
+        walls[centre] = true;
+        let dirs = if pos == start {
+            start_openings[..2].to_vec()
+        } else {
+            openings(pos)
+        };
+        for dir in dirs {
+            walls[centre + dir] = true;
+        }
+    }
+    let mut outside = Grid::filled(size, false);
+    outside[ivec2(0, 0)] = true;
+    let mut queue = vec![ivec2(0, 0)];
+    while let Some(pixel) = queue.pop() {
+        for next in walls.neighbours4(pixel) {
+            if !walls[next] && !outside[next] {
+                outside[next] = true;
+                queue.push(next);
+            }
+        }
+    }
+    let p2 = tiles
+        .positions()
+        .filter(|pos| !main_loop.contains(pos))
+        .filter(|pos| !outside[*pos * 3 + ivec2(2, 2)])
+        .count();
+
+    (p1.to_string(), p2.to_string())
+}
+
+/// Same answers as `solve`, checking each tile off the loop for whether the loop winds
+/// round it instead of counting them with Pick's theorem.
+pub fn solve_winding(input: &str) -> (String, String) {
+    let tiles = Grid::parse(input, |ch| ch);
+    let openings = |pos: IVec2| -> Vec<IVec2> {
+        tiles
+            .get(pos)
+            .and_then(|ch| PipeType::from_char(*ch))
+            .map(PipeType::to_directions)
+            .unwrap_or_default()
+    };
+
+    let start = tiles.find_all(|ch| *ch == 'S')[0];
+    let main_loop = walk_loop(start, |pos| {
+        if pos == start {
+            ORTHOGONAL
+                .into_iter()
+                .filter(|dir| openings(start + dir).contains(&-dir))
                 .collect()
         } else {
-            vec![]
+            openings(pos)
         }
-    })
-    .collect();
-    let p1 = reached_nodes
-        .iter()
-        .map(|item| item.total_cost)
-        .max()
-        .unwrap();
+    });
+    let p1 = main_loop.len() / 2;
+
+    // the loop's tiles are exactly the lattice points on the polygon's edges
+    let polygon = loop_polygon(&main_loop);
+    let p2 = tiles
+        .positions()
+        .filter(|pos| polygon.contains(pos.as_i64vec2()))
+        .count();
 
-    let main_loop: PipeDiagram = reached_nodes
+    (p1.to_string(), p2.to_string())
+}
+
+/// The tiles of the loop in order, going whichever way `openings` lists first at `start`.
+fn walk_loop(start: IVec2, openings: impl Fn(IVec2) -> Vec<IVec2>) -> Vec<IVec2> {
+    let mut main_loop = vec![start];
+    let mut previous = start;
+    let mut current = start + openings(start)[0];
+    while current != start {
+        main_loop.push(current);
+        let next = openings(current)
+            .into_iter()
+            .map(|dir| current + dir)
+            .find(|next| *next != previous)
+            .unwrap();
+        previous = current;
+        current = next;
+    }
+
+    main_loop
+}
+
+fn loop_polygon(main_loop: &[IVec2]) -> Polygon {
+    Polygon::new(main_loop.iter().map(|pos| pos.as_i64vec2()).collect())
+}
+
+/// A random loop surrounded by junk pipes that aren't part of it.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let corners = super::random_loop(rng, size as i32 + 2);
+    let margin = ivec2(1, 1);
+    let pipes: HashMap<IVec2, PipeType> = corners
         .iter()
-        .map(|item| item.node)
-        .map(|pos| (pos, *pipe_diagram.get(&pos).unwrap()))
+        .circular_tuple_windows()
+        .map(|(previous, current, next)| {
+            let pipe = PipeType::from_directions(previous - current, next - current).unwrap();
+            (*current + margin, pipe)
+        })
         .collect();
-    let width = input.lines().next().unwrap().len() as i32;
-    let height = input.lines().count() as i32;
-    let p2 = (1..(width - 1))
-        .cartesian_product(1..(height - 1))
-        .map(|(x, y)| ivec2(x, y))
-        .filter(|pos| !main_loop.keys().contains(pos))
-        .filter(|pos| {
-            let mut intersect_count = 0i32;
-            // traverse from the node to the east, checking for intersections by the even-odd rule
-            for x in (pos.x + 1)..width {
-                let check_pos = pos.with_x(x);
-                if let Some(pipe_type) = main_loop.get(&check_pos) {
-                    // take the position of the intersection to be the upper part of the node
-                    if [
-                        PipeType::NorthSouth,
-                        PipeType::NorthWest,
-                        PipeType::NorthEast,
-                    ]
-                    .contains(pipe_type)
-                    {
-                        intersect_count += 1;
+    let tiles = corners.iter().fold(IVec2::ZERO, |max, pos| max.max(*pos)) + margin * 3;
+    let start = *rng.choose(&corners) + margin;
+
+    let grid: Vec<String> = (0..tiles.y)
+        .map(|y| {
+            (0..tiles.x)
+                .map(|x| {
+                    let pos = ivec2(x, y);
+                    if pos == start {
+                        return 'S';
                     }
-                }
-            }
-            intersect_count % 2 == 1
+                    if let Some(pipe) = pipes.get(&pos) {
+                        return pipe.to_char();
+                    }
+                    // junk right next to the start could make its shape ambiguous
+                    if pos.manhattan_distance(start) == 1 || rng.chance(0.5) {
+                        return '.';
+                    }
+                    *rng.choose(&['|', '-', 'L', 'J', '7', 'F'])
+                })
+                .collect()
         })
-        .count();
-    (p1.to_string(), p2.to_string())
+        .collect();
+
+    grid.join("\n")
 }
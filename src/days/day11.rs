@@ -1,7 +1,6 @@
-use std::fs::read_to_string;
+use glam::{U64Vec2, u64vec2};
 
-use glam::{U64Vec2, u64vec2, uvec2};
-use itertools::Itertools;
+use crate::{grid::Grid, rng::Rng};
 
 const _EX: &str = "...#......
 .......#..
@@ -14,96 +13,81 @@ const _EX: &str = "...#......
 .......#..
 #...#.....";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input11.txt").expect("could not read input");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let mut grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
-    let mut row_index = 0usize;
-    let mut width = grid.first().unwrap().len();
-    let mut row_spaces = vec![];
-    let mut unscaled_row_index = 0usize;
-    while row_index < width {
-        if grid.iter().all(|row| row[row_index] == '.') {
-            grid.iter_mut().for_each(|row| row.insert(row_index, '.'));
-            row_index += 1;
-            width += 1;
-            row_spaces.push(unscaled_row_index);
-        }
-        row_index += 1;
-        unscaled_row_index += 1;
-    }
-    let mut col_index = 0usize;
-    let mut height = grid.len();
-    let mut col_spaces = vec![];
-    let mut unscaled_col_index = 0usize;
-    while col_index < height {
-        if grid[col_index].iter().all(|ch| *ch == '.') {
-            grid.insert(col_index, vec!['.'; width]);
-            col_index += 1;
-            height += 1;
-            col_spaces.push(unscaled_col_index);
-        }
-        col_index += 1;
-        unscaled_col_index += 1;
-    }
-    // let grid_display = grid
-    //     .iter()
-    //     .map(|row| row.iter().collect::<String>())
-    //     .join("\n");
-    // println!("{}", grid_display);
-    let positions: Vec<_> = grid
-        .iter()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.iter().enumerate().flat_map(move |(x, ch)| {
-                if *ch == '#' {
-                    Some(uvec2(x as u32, y as u32))
-                } else {
-                    None
-                }
-            })
-        })
+    let image = Grid::parse(input, |ch| ch == '#');
+    let empty_columns: Vec<u64> = (0..image.width())
+        .filter(|x| image.column(*x).all(|galaxy| !galaxy))
+        .map(|x| x as u64)
+        .collect();
+    let empty_rows: Vec<u64> = (0..image.height())
+        .filter(|y| image.row(*y).iter().all(|galaxy| !galaxy))
+        .map(|y| y as u64)
+        .collect();
+    let galaxies: Vec<_> = image
+        .find_all(|galaxy| *galaxy)
+        .into_iter()
+        .map(|pos| u64vec2(pos.x as u64, pos.y as u64))
         .collect();
-    let p1: u32 = positions
-        .iter()
-        .tuple_combinations()
-        .map(|(a, b)| a.manhattan_distance(*b))
-        .sum();
 
-    let positions: Vec<_> = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.char_indices().flat_map(move |(x, ch)| {
-                if ch == '#' {
-                    Some(u64vec2(x as u64, y as u64))
-                } else {
-                    None
-                }
+    // every empty row or column before a galaxy pushes it along by `expansion - 1`
+    let expand = |expansion: u64| -> Vec<U64Vec2> {
+        galaxies
+            .iter()
+            .map(|pos| {
+                let columns = empty_columns.iter().filter(|x| **x < pos.x).count() as u64;
+                let rows = empty_rows.iter().filter(|y| **y < pos.y).count() as u64;
+                pos + u64vec2(columns, rows) * (expansion - 1)
             })
-        })
-        .collect();
-    let mut old_and_new_pos: Vec<_> = positions.into_iter().map(|pos| (pos, pos)).collect();
-    for space_x in &row_spaces {
-        for (old, new) in &mut old_and_new_pos {
-            if old.x > *space_x as u64 {
-                *new += U64Vec2::X * 999_999;
-            }
-        }
+            .collect()
+    };
+
+    let p1: u64 = super::sum_pairs(&expand(2), |a, b| a.manhattan_distance(*b));
+    let p2: u64 = super::sum_pairs(&expand(1_000_000), |a, b| a.manhattan_distance(*b));
+
+    (p1.to_string(), p2.to_string())
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let image = Grid::parse(input, |ch| ch == '#');
+
+    serde_json::json!({ "size": image.size(), "galaxies": image.find_all(|galaxy| *galaxy) })
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    if !input.contains('#') {
+        return 0;
     }
-    for space_y in &col_spaces {
-        for (old, new) in &mut old_and_new_pos {
-            if old.y > *space_y as u64 {
-                *new += U64Vec2::Y * 999_999;
-            }
+
+    super::grid_len(input, |ch| ch == '.' || ch == '#')
+}
+
+/// Galaxies scattered around some rows and columns that are left empty to expand.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let width = 10 * size;
+    loop {
+        let empty_rows: Vec<bool> = (0..width).map(|_| rng.chance(0.2)).collect();
+        let empty_cols: Vec<bool> = (0..width).map(|_| rng.chance(0.2)).collect();
+        let rows: Vec<String> = empty_rows
+            .iter()
+            .map(|&empty_row| {
+                empty_cols
+                    .iter()
+                    .map(|&empty_col| {
+                        if !empty_row && !empty_col && rng.chance(0.15) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let text = rows.join("\n");
+        if text.matches('#').count() >= 2 {
+            return text;
         }
     }
-    let p2: u64 = old_and_new_pos
-        .iter()
-        .tuple_combinations()
-        .map(|(a, b)| a.1.manhattan_distance(b.1))
-        .sum();
-
-    (p1.to_string(), p2.to_string())
 }
@@ -1,27 +1,47 @@
-use std::{collections::HashMap, fs::read_to_string};
+use std::{collections::HashMap, fmt, io::BufRead};
+
+use itertools::Itertools;
 
 use nom::{
     IResult, Parser,
-    branch::alt,
     bytes::complete::tag,
-    character::complete::{self, line_ending, space1},
-    multi::{many1, separated_list1},
+    character::complete::{self, space1},
+    multi::separated_list1,
     sequence::separated_pair,
 };
 
-#[derive(Debug)]
+use serde::Serialize;
+
+use crate::{parse, rng::Rng};
+
+#[derive(Debug, PartialEq, Serialize)]
 struct Row {
     springs: Vec<Condition>,
     groups: Vec<usize>,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
 enum Condition {
     Operational,
     Damaged,
     Unknown,
 }
 
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let springs: String = self
+            .springs
+            .iter()
+            .map(|spring| match spring {
+                Condition::Operational => '.',
+                Condition::Damaged => '#',
+                Condition::Unknown => '?',
+            })
+            .collect();
+        write!(f, "{springs} {}", self.groups.iter().join(","))
+    }
+}
+
 const _EX: &str = "???.### 1,1,3
 .??..??...?##. 1,1,3
 ?#?#?#?#?#?#?#? 1,3,1,6
@@ -29,31 +49,134 @@ const _EX: &str = "???.### 1,1,3
 ????.######..#####. 1,6,5
 ?###???????? 3,2,1";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input12.txt").expect("input could not be read");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
+    let (p1, p2) = super::sum_lines(input, |line| row_values(line, count_memoized));
 
-    let (_, rows) = parse_rows(&input).expect("epic parse fail");
-
-    let p1: usize = rows
-        .iter()
-        .map(|row| solve_row(&row.springs, &row.groups, &mut HashMap::new()))
-        .sum();
+    (p1.to_string(), p2.to_string())
+}
 
-    let rows_p2 = rows.iter().map(|row| {
-        let springs = vec![row.springs.clone(); 5].join(&Condition::Unknown);
-        let groups = row.groups.repeat(5);
+/// Same as `solve`, but with part 2's rows unfolded `times` over instead of 5.
+pub fn solve_unfolded(input: &str, times: usize) -> (String, String) {
+    let (p1, p2) = input.lines().fold((0usize, 0usize), |(p1, p2), line| {
+        let row = parse::all(line, parse_row).expect("epic parse fail");
 
-        Row { springs, groups }
+        (
+            p1 + count_memoized(&row),
+            p2 + count_memoized(&unfold(&row, times)),
+        )
     });
 
-    let p2: usize = rows_p2
-        .map(|row| solve_row(&row.springs, &row.groups, &mut HashMap::new()))
-        .sum();
+    (p1.to_string(), p2.to_string())
+}
+
+/// Same answers as `solve`, filling a table from the end of each row instead of memoising.
+pub fn solve_table(input: &str) -> (String, String) {
+    let (p1, p2) = super::sum_lines(input, |line| row_values(line, count_table));
+
+    (p1.to_string(), p2.to_string())
+}
+
+pub fn solve_stream(input: &mut dyn BufRead) -> (String, String) {
+    let (p1, p2) = super::stream_lines(input).fold((0usize, 0usize), |(p1, p2), line| {
+        let (row_p1, row_p2) = row_values(&line, count_memoized);
+        (p1 + row_p1, p2 + row_p2)
+    });
 
     (p1.to_string(), p2.to_string())
 }
 
+/// Arrangements of the row as it is, and unfolded.
+fn row_values(line: &str, count: fn(&Row) -> usize) -> (usize, usize) {
+    let row = parse::all(line, parse_row).expect("epic parse fail");
+
+    (count(&row), count(&unfold(&row, 5)))
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_rows(input))
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_rows).expect("epic parse fail");
+
+    serde_json::json!(model)
+}
+
+/// Rows made by hiding parts of a real arrangement, so each has at least one.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let rows: Vec<String> = (0..10 * size)
+        .map(|_| {
+            let groups: Vec<usize> = (0..rng.range(1..5))
+                .map(|_| rng.range(1..5) as usize)
+                .collect();
+            let mut springs = ".".repeat(rng.below(3));
+            for (i, group) in groups.iter().enumerate() {
+                if i > 0 {
+                    springs += &".".repeat(rng.range(1..4) as usize);
+                }
+                springs += &"#".repeat(*group);
+            }
+            springs += &".".repeat(rng.below(3));
+
+            let springs: String = springs
+                .chars()
+                .map(|spring| if rng.chance(0.4) { '?' } else { spring })
+                .collect();
+            format!("{springs} {}", groups.iter().join(","))
+        })
+        .collect();
+
+    rows.join("\n")
+}
+
+pub fn round_trip(input: &str) -> Result<(), String> {
+    let rows = parse::all(input, parse_rows).expect("epic parse fail");
+    let printed = rows.iter().join("\n");
+
+    super::check_round_trip(&rows, &printed, parse_rows(&printed))
+}
+
+fn unfold(row: &Row, times: usize) -> Row {
+    let springs = vec![row.springs.clone(); times].join(&Condition::Unknown);
+    let groups = row.groups.repeat(times);
+
+    Row { springs, groups }
+}
+
+fn count_memoized(row: &Row) -> usize {
+    solve_row(&row.springs, &row.groups, &mut HashMap::new())
+}
+
+/// `ways[i][j]` is the number of arrangements of `springs[i..]` into `groups[j..]`.
+fn count_table(row: &Row) -> usize {
+    let (springs, groups) = (&row.springs, &row.groups);
+    let (n, m) = (springs.len(), groups.len());
+    let mut ways = vec![vec![0usize; m + 1]; n + 1];
+    ways[n][m] = 1;
+
+    for i in (0..n).rev() {
+        for j in 0..=m {
+            let mut total = 0;
+            if springs[i] != Condition::Damaged {
+                total += ways[i + 1][j];
+            }
+            if j < m && springs[i] != Condition::Operational {
+                let end = i + groups[j];
+                if end <= n
+                    && !springs[i..end].contains(&Condition::Operational)
+                    && springs.get(end) != Some(&Condition::Damaged)
+                {
+                    total += ways[(end + 1).min(n)][j + 1];
+                }
+            }
+            ways[i][j] = total;
+        }
+    }
+
+    ways[0][0]
+}
+
 fn solve_row<'a>(
     springs: &'a [Condition],
     groups: &'a [usize],
@@ -103,7 +226,7 @@ fn solve_row<'a>(
 }
 
 fn parse_rows(input: &str) -> IResult<&str, Vec<Row>> {
-    separated_list1(line_ending, parse_row).parse(input)
+    parse::lines(parse_row).parse(input)
 }
 
 fn parse_row(input: &str) -> IResult<&str, Row> {
@@ -114,18 +237,13 @@ fn parse_row(input: &str) -> IResult<&str, Row> {
 }
 
 fn parse_springs(input: &str) -> IResult<&str, Vec<Condition>> {
-    let (input, springs) = many1(alt((tag("#"), tag("."), tag("?")))).parse(input)?;
-    let springs = springs
-        .iter()
-        .map(|&spring| match spring {
-            "." => Condition::Operational,
-            "#" => Condition::Damaged,
-            "?" => Condition::Unknown,
-            _ => unreachable!(),
-        })
-        .collect();
-
-    Ok((input, springs))
+    parse::cells(|spring| match spring {
+        '.' => Some(Condition::Operational),
+        '#' => Some(Condition::Damaged),
+        '?' => Some(Condition::Unknown),
+        _ => None,
+    })
+    .parse(input)
 }
 
 fn parse_groups(input: &str) -> IResult<&str, Vec<usize>> {
@@ -1,21 +1,26 @@
-use std::fs::read_to_string;
-
-use nom::{
-    IResult, Parser,
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::line_ending,
-    multi::{many1, separated_list1},
-    sequence::pair,
-};
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+use std::fmt;
+
+use glam::ivec2;
+use nom::{IResult, Parser};
+
+use serde::Serialize;
+
+use crate::{grid::Grid, parse, rng::Rng};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
 enum Terrain {
     Ash,
     Rock,
 }
 
-type Grid = Vec<Vec<Terrain>>;
+impl fmt::Display for Terrain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Terrain::Ash => write!(f, "."),
+            Terrain::Rock => write!(f, "#"),
+        }
+    }
+}
 
 const _EX: &str = "#.##..##.
 ..#.##.#.
@@ -33,11 +38,10 @@ const _EX: &str = "#.##..##.
 ..##..###
 #....#..#";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input13.txt").expect("could not read input");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let (_, grids) = parse_grids(&input).expect("epic parse fail");
+    let grids = parse::all(input, parse_grids).expect("epic parse fail");
     // dbg!(&grids);
 
     // dbg!(check_horizontal_reflection(&grids[0]));
@@ -65,13 +69,100 @@ pub fn solve() -> (String, String) {
     (p1.to_string(), p2.to_string())
 }
 
-fn check_horizontal_reflection(grid: &Grid) -> Vec<usize> {
+pub fn parsed_len(input: &str) -> usize {
+    match parse_grids(input) {
+        // a single block is indistinguishable from the other `#`/`.` grids
+        Ok((rest, grids)) if grids.len() > 1 => input.len() - rest.len(),
+        _ => 0,
+    }
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_grids).expect("epic parse fail");
+
+    serde_json::json!(model)
+}
+
+/// Patterns with exactly one clean reflection and exactly one smudged one: a horizontal and
+/// a vertical line are both mirrored perfectly, then one cell the vertical line doesn't
+/// reach is flipped to smudge the horizontal one.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let patterns: Vec<String> = (0..size + 1)
+        .map(|_| {
+            loop {
+                let width = rng.range(5..16) as usize;
+                let height = rng.range(5..16) as usize;
+                let smudged_row = rng.range(1..height as i64) as usize;
+                let clean_col = rng.range(1..width as i64) as usize;
+                if clean_col * 2 == width {
+                    continue;
+                }
+                let mirror = |pos: usize, line: usize, len: usize| {
+                    (2 * line)
+                        .checked_sub(pos + 1)
+                        .filter(|mirrored| *mirrored < len)
+                };
+
+                let mut grid: Vec<Vec<Option<Terrain>>> = vec![vec![None; width]; height];
+                for y in 0..height {
+                    for x in 0..width {
+                        if grid[y][x].is_some() {
+                            continue;
+                        }
+                        let terrain = *rng.choose(&[Terrain::Ash, Terrain::Rock]);
+                        for y in [Some(y), mirror(y, smudged_row, height)]
+                            .into_iter()
+                            .flatten()
+                        {
+                            for x in [Some(x), mirror(x, clean_col, width)].into_iter().flatten() {
+                                grid[y][x] = Some(terrain);
+                            }
+                        }
+                    }
+                }
+                let mut grid = Grid::from_rows(
+                    grid.into_iter()
+                        .map(|row| row.into_iter().map(Option::unwrap).collect())
+                        .collect(),
+                );
+
+                let y = rng.below(height);
+                let x = rng.below(width);
+                if mirror(y, smudged_row, height).is_none() || mirror(x, clean_col, width).is_some()
+                {
+                    continue;
+                }
+                let pos = ivec2(x as i32, y as i32);
+                grid[pos] = match grid[pos] {
+                    Terrain::Ash => Terrain::Rock,
+                    Terrain::Rock => Terrain::Ash,
+                };
+                if rng.chance(0.5) {
+                    grid = grid.transpose();
+                }
+
+                // other lines can line up by chance
+                let clean = check_horizontal_reflection(&grid).len()
+                    + check_vertical_reflection(&grid).len();
+                let smudged =
+                    check_horizontal_smudge(&grid).len() + check_vertical_smudge(&grid).len();
+                if clean == 1 && smudged == 1 {
+                    break grid.to_string();
+                }
+            }
+        })
+        .collect();
+
+    patterns.join("\n\n")
+}
+
+fn check_horizontal_reflection(grid: &Grid<Terrain>) -> Vec<usize> {
     let mut reflection_lines = vec![];
 
-    for i in 1..grid.len() {
-        if (0..i).rev().zip(i..grid.len()).all(|(a, b)| {
-            // dbg!(&grid[a], &grid[b]);
-            grid[a] == grid[b]
+    for i in 1..grid.height() {
+        if (0..i).rev().zip(i..grid.height()).all(|(a, b)| {
+            // dbg!(grid.row(a), grid.row(b));
+            grid.row(a) == grid.row(b)
         }) {
             reflection_lines.push(i);
         }
@@ -80,34 +171,32 @@ fn check_horizontal_reflection(grid: &Grid) -> Vec<usize> {
     reflection_lines
 }
 
-fn check_vertical_reflection(grid: &Grid) -> Vec<usize> {
-    let new_grid: Vec<Vec<Terrain>> = (0..grid[0].len())
-        .map(|i| grid.iter().map(|inner| inner[i]).collect::<Vec<Terrain>>())
-        .collect();
-
-    check_horizontal_reflection(&new_grid)
+fn check_vertical_reflection(grid: &Grid<Terrain>) -> Vec<usize> {
+    check_horizontal_reflection(&grid.transpose())
 }
 
-fn check_horizontal_smudge(grid: &Grid) -> Vec<usize> {
+fn check_horizontal_smudge(grid: &Grid<Terrain>) -> Vec<usize> {
     let mut reflection_lines = vec![];
 
-    'outer: for i in 1..grid.len() {
+    'outer: for i in 1..grid.height() {
         let mut smudges = 1i32;
-        smudges -= grid[i]
+        smudges -= grid
+            .row(i)
             .iter()
-            .zip(grid[i - 1].iter())
+            .zip(grid.row(i - 1))
             .filter(|(a, b)| a != b)
             .count() as i32;
         if smudges < 0 {
             continue;
         }
         for j in 1..=i {
-            if i + j >= grid.len() || (i as isize - j as isize - 1) < 0 {
+            if i + j >= grid.height() || (i as isize - j as isize - 1) < 0 {
                 break;
             }
-            smudges -= grid[i - j - 1]
+            smudges -= grid
+                .row(i - j - 1)
                 .iter()
-                .zip(grid[i + j].iter())
+                .zip(grid.row(i + j))
                 .filter(|(a, b)| a != b)
                 .count() as i32;
             if smudges < 0 {
@@ -122,32 +211,19 @@ fn check_horizontal_smudge(grid: &Grid) -> Vec<usize> {
     reflection_lines
 }
 
-fn check_vertical_smudge(grid: &Grid) -> Vec<usize> {
-    let new_grid: Vec<Vec<Terrain>> = (0..grid[0].len())
-        .map(|i| grid.iter().map(|inner| inner[i]).collect::<Vec<Terrain>>())
-        .collect();
-
-    check_horizontal_smudge(&new_grid)
-}
-
-fn parse_grids(input: &str) -> IResult<&str, Vec<Grid>> {
-    separated_list1(pair(line_ending, line_ending), parse_grid).parse(input)
+fn check_vertical_smudge(grid: &Grid<Terrain>) -> Vec<usize> {
+    check_horizontal_smudge(&grid.transpose())
 }
 
-fn parse_grid(input: &str) -> IResult<&str, Grid> {
-    separated_list1(line_ending, parse_row).parse(input)
+fn parse_grids(input: &str) -> IResult<&str, Vec<Grid<Terrain>>> {
+    parse::blocks(parse_grid).parse(input)
 }
 
-fn parse_row(input: &str) -> IResult<&str, Vec<Terrain>> {
-    let (input, raw_terrains) = many1(alt((tag("#"), tag(".")))).parse(input)?;
-    let terrains = raw_terrains
-        .iter()
-        .map(|terrain| match *terrain {
-            "." => Terrain::Ash,
-            "#" => Terrain::Rock,
-            _ => unreachable!(),
-        })
-        .collect();
-
-    Ok((input, terrains))
+fn parse_grid(input: &str) -> IResult<&str, Grid<Terrain>> {
+    parse::grid(|terrain| match terrain {
+        '.' => Some(Terrain::Ash),
+        '#' => Some(Terrain::Rock),
+        _ => None,
+    })
+    .parse(input)
 }
@@ -1,4 +1,4 @@
-use std::{fs::read_to_string, num::Wrapping};
+use std::num::Wrapping;
 
 use nom::{
     IResult, Parser,
@@ -8,6 +8,10 @@ use nom::{
     multi::separated_list1,
 };
 
+use serde::Serialize;
+
+use crate::{parse, rng::Rng};
+
 type Box<'a> = Vec<Lens<'a>>;
 
 #[derive(Debug, Clone, Copy)]
@@ -16,6 +20,7 @@ struct Lens<'a> {
     focal_len: u8,
 }
 
+#[derive(Serialize)]
 enum Action {
     Remove,
     FocalLen(u8),
@@ -23,17 +28,16 @@ enum Action {
 
 const _EX: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input15.txt").expect("input could not be read");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let (_, sequence) = parse_sequence(&input).expect("epic parse fail");
+    let sequence = parse::all(input, parse_sequence).expect("epic parse fail");
 
     let p1: u32 = sequence.iter().map(|step| hash(step) as u32).sum();
 
     let mut boxes = vec![Box::new(); 256];
     for step in sequence {
-        let (_, (label, action)) = parse_step(step).expect("epic parse fail");
+        let (label, action) = parse::all(step, parse_step).expect("epic parse fail");
         let hash = hash(label);
         if let Some(lens_box) = boxes.get_mut(hash as usize) {
             match action {
@@ -66,6 +70,51 @@ pub fn solve() -> (String, String) {
     (p1.to_string(), p2.to_string())
 }
 
+pub fn parsed_len(input: &str) -> usize {
+    let Ok((_, sequence)) = parse_sequence(input.trim_end()) else {
+        return 0;
+    };
+
+    sequence
+        .iter()
+        .take_while(|step| matches!(parse_step(step), Ok(("", _))))
+        .map(|step| step.len() + 1)
+        .sum()
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let sequence = parse::all(input, parse_sequence).expect("epic parse fail");
+    let steps: Vec<_> = sequence
+        .iter()
+        .map(|step| parse::all(step, parse_step).expect("epic parse fail"))
+        .collect();
+
+    serde_json::json!(steps)
+}
+
+/// Steps drawn from a small pool of labels, so lenses get replaced and removed.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let labels: Vec<String> = (0..4 * size)
+        .map(|_| {
+            (0..rng.range(1..5))
+                .map(|_| (b'a' + rng.below(26) as u8) as char)
+                .collect()
+        })
+        .collect();
+    let steps: Vec<String> = (0..20 * size)
+        .map(|_| {
+            let label = rng.choose(&labels);
+            if rng.chance(0.3) {
+                format!("{label}-")
+            } else {
+                format!("{label}={}", rng.range(1..10))
+            }
+        })
+        .collect();
+
+    steps.join(",")
+}
+
 fn hash(input: &str) -> u8 {
     let input = input.as_bytes();
     input
@@ -75,7 +124,7 @@ fn hash(input: &str) -> u8 {
 }
 
 fn parse_sequence(input: &str) -> IResult<&str, Vec<&str>> {
-    separated_list1(tag(","), is_not(",")).parse(input)
+    separated_list1(tag(","), is_not(",\r\n")).parse(input)
 }
 
 fn parse_step(input: &str) -> IResult<&str, (&str, Action)> {
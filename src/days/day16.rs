@@ -1,49 +1,34 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs::read_to_string,
-};
+use std::collections::HashSet;
 
 use glam::{IVec2, ivec2};
 use itertools::Itertools;
 use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    direction::Direction,
+    grid::{Grid, Storage},
+    rng::Rng,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Serialize)]
 enum Obstacle {
     Mirror(Mirror),
     Splitter(Splitter),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Serialize)]
 enum Mirror {
     NE,
     NW,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Serialize)]
 enum Splitter {
     NS,
     EW,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-enum Direction {
-    N,
-    S,
-    E,
-    W,
-}
-
-impl Direction {
-    fn to_ivec2(self) -> IVec2 {
-        match self {
-            Self::N => ivec2(0, -1),
-            Self::S => ivec2(0, 1),
-            Self::E => ivec2(1, 0),
-            Self::W => ivec2(-1, 0),
-        }
-    }
-}
-
 const _EX: &str = r".|...\....
 |.-.\.....
 .....|-...
@@ -55,34 +40,43 @@ const _EX: &str = r".|...\....
 .|....-|.\
 ..//.|....";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input16.txt").expect("input could not be read");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let obstacles: HashMap<_, _> = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.char_indices().filter_map(move |(x, ch)| match ch {
-                '/' => Some((ivec2(x as i32, y as i32), Obstacle::Mirror(Mirror::NE))),
-                '\\' => Some((ivec2(x as i32, y as i32), Obstacle::Mirror(Mirror::NW))),
-                '|' => Some((ivec2(x as i32, y as i32), Obstacle::Splitter(Splitter::NS))),
-                '-' => Some((ivec2(x as i32, y as i32), Obstacle::Splitter(Splitter::EW))),
-                _ => None,
-            })
-        })
-        .collect();
-    let width = input.lines().next().unwrap().len() as i32;
-    let height = input.lines().count() as i32;
+    let obstacles = parse_obstacles(input);
+
+    solve_obstacles(&obstacles)
+}
+
+/// Same answers as `solve` with the obstacles in a hash map instead. It benches somewhat
+/// slower than the dense grid, though by less than day 17's sparse costs do, since the beams
+/// spend most of their time in the `visited` set rather than looking up obstacles.
+pub fn solve_sparse(input: &str) -> (String, String) {
+    let obstacles = parse_obstacles(input);
+
+    solve_obstacles(&obstacles.to_sparse())
+}
+
+fn parse_obstacles(input: &str) -> Grid<Option<Obstacle>> {
+    Grid::parse(input, |ch| match ch {
+        '/' => Some(Obstacle::Mirror(Mirror::NE)),
+        '\\' => Some(Obstacle::Mirror(Mirror::NW)),
+        '|' => Some(Obstacle::Splitter(Splitter::NS)),
+        '-' => Some(Obstacle::Splitter(Splitter::EW)),
+        _ => None,
+    })
+}
+
+fn solve_obstacles(obstacles: &(impl Storage<Option<Obstacle>> + Sync)) -> (String, String) {
+    let (
+        _,
+        IVec2 {
+            x: width,
+            y: height,
+        },
+    ) = obstacles.bounds().unwrap();
     let mut visited = HashSet::new();
-    traverse(
-        ivec2(0, 0),
-        Direction::E,
-        &obstacles,
-        width,
-        height,
-        &mut visited,
-    );
+    traverse(ivec2(0, 0), Direction::E, obstacles, &mut visited);
     let p1 = visited
         .iter()
         .map(|(pos, _)| pos)
@@ -106,7 +100,7 @@ pub fn solve() -> (String, String) {
         .into_par_iter()
         .map(|(pos, dir)| {
             let mut visited = HashSet::new();
-            traverse(pos, dir, &obstacles, width, height, &mut visited);
+            traverse(pos, dir, obstacles, &mut visited);
 
             visited
                 .iter()
@@ -120,61 +114,69 @@ pub fn solve() -> (String, String) {
     (p1.to_string(), p2.to_string())
 }
 
+pub fn model(input: &str) -> serde_json::Value {
+    serde_json::json!(parse_obstacles(input))
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    if !input.contains(['|', '-', '/', '\\']) {
+        return 0;
+    }
+
+    super::grid_len(input, |ch| ".|-/\\".contains(ch))
+}
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let width = 10 * size;
+    let rows: Vec<String> = (0..width)
+        .map(|_| {
+            (0..width)
+                .map(|_| {
+                    if rng.chance(0.8) {
+                        '.'
+                    } else {
+                        *rng.choose(&['|', '-', '/', '\\'])
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    rows.join("\n")
+}
+
 fn traverse(
     pos: IVec2,
     dir: Direction,
-    obstacles: &HashMap<IVec2, Obstacle>,
-    width: i32,
-    height: i32,
+    obstacles: &impl Storage<Option<Obstacle>>,
     visited: &mut HashSet<(IVec2, Direction)>,
 ) {
     // dbg!(pos, dir);
-    if visited.contains(&(pos, dir))
-        || !(0..width).contains(&pos.x)
-        || !(0..height).contains(&pos.y)
-    {
+    let Some(current_obstacle) = obstacles.get(pos) else {
+        return;
+    };
+    if visited.contains(&(pos, dir)) {
         return;
     }
 
     visited.insert((pos, dir));
     // println!("{}\n", display_visited(&visited.iter().map(|(pos, _)| pos).collect(), width as usize, height as usize));
 
-    let current_obstacle = obstacles.get(&pos);
-
-    use Direction as D;
     let next_dirs = match current_obstacle {
         None => vec![dir],
-        Some(Obstacle::Mirror(Mirror::NE)) => match dir {
-            D::N => vec![D::E],
-            D::E => vec![D::N],
-            D::S => vec![D::W],
-            D::W => vec![D::S],
-        },
-        Some(Obstacle::Mirror(Mirror::NW)) => match dir {
-            D::N => vec![D::W],
-            D::W => vec![D::N],
-            D::S => vec![D::E],
-            D::E => vec![D::S],
-        },
-        Some(Obstacle::Splitter(Splitter::NS)) => match dir {
-            D::N | D::S => vec![dir],
-            D::E | D::W => vec![D::N, D::S],
-        },
-        Some(Obstacle::Splitter(Splitter::EW)) => match dir {
-            D::E | D::W => vec![dir],
-            D::N | D::S => vec![D::E, D::W],
-        },
+        // `/` turns beams going north or south right and beams going east or west left,
+        // `\` the other way round
+        Some(Obstacle::Mirror(Mirror::NE)) if dir.is_vertical() => vec![dir.turn_right()],
+        Some(Obstacle::Mirror(Mirror::NE)) => vec![dir.turn_left()],
+        Some(Obstacle::Mirror(Mirror::NW)) if dir.is_vertical() => vec![dir.turn_left()],
+        Some(Obstacle::Mirror(Mirror::NW)) => vec![dir.turn_right()],
+        Some(Obstacle::Splitter(Splitter::NS)) if dir.is_vertical() => vec![dir],
+        Some(Obstacle::Splitter(Splitter::EW)) if !dir.is_vertical() => vec![dir],
+        Some(Obstacle::Splitter(_)) => vec![dir.turn_left(), dir.turn_right()],
     };
 
     for next_dir in next_dirs {
-        traverse(
-            pos + next_dir.to_ivec2(),
-            next_dir,
-            obstacles,
-            width,
-            height,
-            visited,
-        );
+        traverse(pos + next_dir.to_ivec2(), next_dir, obstacles, visited);
     }
 }
 
@@ -1,49 +1,99 @@
-use std::{
-    cmp::{Ordering, Reverse}, collections::{BinaryHeap, HashMap, HashSet}, fs::read_to_string
-};
+use std::fmt;
 
 use glam::{IVec2, ivec2};
+use serde::Serialize;
+
+use crate::{
+    direction::Direction as D,
+    explain::{self, Step},
+    grid::{Grid, Storage},
+    rng::Rng,
+    search::{self, SearchState},
+};
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
-enum D {
-    N,
-    S,
-    E,
-    W,
-}
-
-impl D {
-    fn to_ivec2(self) -> IVec2 {
-        match self {
-            D::N => ivec2(0, -1),
-            D::S => ivec2(0, 1),
-            D::E => ivec2(1, 0),
-            D::W => ivec2(-1, 0),
-        }
-    }
-}
-
+/// The crucible's position, the way it's heading and how many blocks it's gone that way.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-struct Step {
+struct Crucible {
     pos: IVec2,
     dir: D,
-    cur_cost: u32,
     step_count: u8,
 }
 
-impl Ord for Step {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.pos == other.pos {
-            (self.dir, self.cur_cost, self.step_count).cmp(&(other.dir, other.cur_cost, other.step_count))
+/// What a crucible moves over: the heat lost entering each block, and how far it has to and
+/// may go in a straight line.
+struct City<'a, S> {
+    costs: &'a S,
+    end: IVec2,
+    min_steps: u8,
+    max_steps: u8,
+}
+
+impl<S: Storage<u32>> SearchState<City<'_, S>> for Crucible {
+    fn successors(&self, city: &City<S>) -> Vec<(Crucible, u64)> {
+        let next_dirs = if self.step_count < city.min_steps {
+            vec![self.dir]
+        } else if self.step_count >= city.max_steps {
+            vec![self.dir.turn_left(), self.dir.turn_right()]
         } else {
-            (self.pos.x, self.pos.y).cmp(&(other.pos.x, other.pos.y))
+            vec![self.dir.turn_left(), self.dir.turn_right(), self.dir]
+        };
+
+        next_dirs
+            .into_iter()
+            .filter_map(|next_dir| {
+                let next_pos = self.pos + next_dir.to_ivec2();
+                let next_cost = city.costs.get(next_pos)?;
+                let next = Crucible {
+                    pos: next_pos,
+                    dir: next_dir,
+                    step_count: if self.dir == next_dir {
+                        self.step_count + 1
+                    } else {
+                        1
+                    },
+                };
+                Some((next, *next_cost as u64))
+            })
+            .collect()
+    }
+
+    /// Every block costs at least 1, so the distance left is a lower bound.
+    fn heuristic(&self, city: &City<S>) -> u64 {
+        self.pos.manhattan_distance(city.end) as u64
+    }
+
+    fn is_goal(&self, city: &City<S>) -> bool {
+        self.pos == city.end && self.step_count >= city.min_steps
+    }
+}
+
+/// The cheapest way a part's crucibles found across the city, as the blocks it turns at.
+#[derive(Serialize)]
+struct Route {
+    part: u8,
+    heat_loss: u64,
+    turns: Vec<(IVec2, D)>,
+    expanded: usize,
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "heat loss {} after trying {} states",
+            self.heat_loss, self.expanded
+        )?;
+        for (pos, dir) in &self.turns {
+            write!(f, "\n  ({}, {}) heading {dir:?}", pos.x, pos.y)?;
         }
+
+        Ok(())
     }
 }
 
-impl PartialOrd for Step {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Step for Route {
+    fn part(&self) -> Option<u8> {
+        Some(self.part)
     }
 }
 
@@ -61,89 +111,113 @@ const _EX: &str = "2413432311323
 2546548887735
 4322674655533";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input17.txt").expect("could not read input");
-
-    let costs: HashMap<_, _> = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.char_indices()
-                .map(move |(x, ch)| (ivec2(x as i32, y as i32), ch.to_digit(10).unwrap()))
-        })
-        .collect();
-    let width = input.lines().next().unwrap().len();
-    let height = input.lines().count();
-    dbg!(width, height);
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
+
+    let costs = Grid::parse(input, |ch| ch.to_digit(10).unwrap());
+
+    solve_costs(&costs, Algorithm::AStar)
+}
+
+/// Same answers as `solve` with the costs in a hash map instead, to bench the dense grid against.
+pub fn solve_sparse(input: &str) -> (String, String) {
+    let costs = Grid::parse(input, |ch| ch.to_digit(10).unwrap());
 
-    let p1 = find_cheapest_path(&costs, ivec2(width as i32 - 1, height as i32 - 1), 1, 3);
+    solve_costs(&costs.to_sparse(), Algorithm::AStar)
+}
+
+/// Same answers as `solve` without the heuristic pointing the way to the end.
+pub fn solve_dijkstra(input: &str) -> (String, String) {
+    let costs = Grid::parse(input, |ch| ch.to_digit(10).unwrap());
+
+    solve_costs(&costs, Algorithm::Dijkstra)
+}
+
+/// Same answers as `solve_dijkstra`, with a bucket for each heat loss instead of a heap.
+/// Blocks never cost more than 9, so there are never many buckets ahead of the current one.
+pub fn solve_bucket(input: &str) -> (String, String) {
+    let costs = Grid::parse(input, |ch| ch.to_digit(10).unwrap());
+
+    solve_costs(&costs, Algorithm::Bucket)
+}
 
-    let p2 = find_cheapest_path(&costs, ivec2(width as i32 - 1, height as i32 - 1), 4, 10);
+/// Which search the crucibles find their way with.
+#[derive(Clone, Copy)]
+enum Algorithm {
+    AStar,
+    Dijkstra,
+    Bucket,
+}
+
+fn solve_costs(costs: &impl Storage<u32>, algorithm: Algorithm) -> (String, String) {
+    let (_, size) = costs.bounds().unwrap();
+    let end = size - ivec2(1, 1);
+
+    let p1 = find_cheapest_path(costs, end, 1, 3, algorithm, 1);
+
+    let p2 = find_cheapest_path(costs, end, 4, 10, algorithm, 2);
 
     (p1.to_string(), p2.to_string())
 }
 
-fn find_cheapest_path(
-    costs: &HashMap<IVec2, u32>,
-    end: IVec2,
-    min_steps: u8,
-    max_steps: u8
-) -> u32 {
-    let mut queue = BinaryHeap::new();
-    queue.push(Reverse((0u32, Step{ pos: ivec2(0, 0), dir: D::E, cur_cost: 0, step_count: 0})));
-    queue.push(Reverse((0, Step{ pos: ivec2(0, 0), dir: D::S, cur_cost: 0, step_count: 0})));
-
-    let mut visited = HashSet::new();
-    visited.insert((ivec2(0, 0), D::E, 0u8));
-    visited.insert((ivec2(0, 0), D::S, 0));
-
-    while let Some(cur_queue_item) = queue.pop() {
-        let cur_step = cur_queue_item.0.1;
-
-        if cur_step.pos == end {
-            if cur_step.step_count < min_steps {
-                continue;
-            } else {
-                return cur_step.cur_cost;
-            }
-        }
+pub fn model(input: &str) -> serde_json::Value {
+    serde_json::json!(Grid::parse(input, |ch| ch.to_digit(10).unwrap()))
+}
 
-        let next_dirs = if cur_step.step_count < min_steps {
-            vec![cur_step.dir]
-        } else if cur_step.step_count >= max_steps {
-            match cur_step.dir {
-                D::N | D::S => vec![D::E, D::W],
-                D::E | D::W => vec![D::N, D::S],
-            }
-        } else {
-            match cur_step.dir {
-                D::N | D::S => vec![D::E, D::W, cur_step.dir],
-                D::E | D::W => vec![D::N, D::S, cur_step.dir],
-            }
-        };
+pub fn parsed_len(input: &str) -> usize {
+    super::grid_len(input, |ch| ch.is_ascii_digit())
+}
 
-        for next_dir in next_dirs {
-            let next_pos = cur_step.pos + next_dir.to_ivec2();
-            let Some(next_cost) = costs.get(&next_pos) else {
-                continue;
-            };
-            let next_step = Step {
-                pos: next_pos,
-                dir: next_dir,
-                cur_cost: cur_step.cur_cost + next_cost,
-                step_count: if cur_step.dir == next_dir {
-                    cur_step.step_count + 1
-                } else {
-                    1
-                }
-            };
-            let heuristic = cur_step.cur_cost + next_cost + next_step.pos.manhattan_distance(end);
-            if visited.insert((next_step.pos, next_step.dir, next_step.step_count)) {
-                queue.push(Reverse((heuristic, next_step)));
-            }
-        }
-    }
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let width = 10 * size;
+    let rows: Vec<String> = (0..width)
+        .map(|_| (0..width).map(|_| rng.range(1..10).to_string()).collect())
+        .collect();
+
+    rows.join("\n")
+}
 
-    u32::MAX
+fn find_cheapest_path(
+    costs: &impl Storage<u32>,
+    end: IVec2,
+    min_steps: u8,
+    max_steps: u8,
+    algorithm: Algorithm,
+    part: u8,
+) -> u64 {
+    let city = City {
+        costs,
+        end,
+        min_steps,
+        max_steps,
+    };
+    let starts = [D::E, D::S].map(|dir| Crucible {
+        pos: ivec2(0, 0),
+        dir,
+        step_count: 0,
+    });
+
+    let outcome = match algorithm {
+        Algorithm::AStar => search::astar(starts, &city),
+        Algorithm::Dijkstra => search::dijkstra(starts, &city),
+        Algorithm::Bucket => search::bucket(starts, &city),
+    };
+    let Some(heat_loss) = outcome.cost() else {
+        return u64::MAX;
+    };
+    explain::step(|| Route {
+        part,
+        heat_loss,
+        // the first step out of the corner counts as a turn too
+        turns: outcome
+            .path()
+            .unwrap()
+            .windows(2)
+            .filter(|pair| pair[1].step_count == 1)
+            .map(|pair| (pair[0].pos, pair[1].dir))
+            .collect(),
+        expanded: outcome.expanded(),
+    });
+
+    heat_loss
 }
@@ -1,44 +1,36 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs::read_to_string,
-};
-
-use glam::{IVec2, UVec2, ivec2, uvec2};
+use glam::IVec2;
 use itertools::Itertools;
 use nom::{
     IResult, Parser,
     bytes::complete::{tag, take_while_m_n},
-    character::complete::{self, line_ending, one_of, space1},
+    character::complete::{self, one_of, space1},
     combinator::map_res,
-    multi::separated_list1,
 };
 
-#[derive(Debug, Clone, Copy)]
+use serde::Serialize;
+
+use crate::{direction::Direction, parse, polygon::Polygon, rng::Rng};
+
+#[derive(Debug, Clone, Copy, Serialize)]
 struct Instruction {
-    dir: D,
+    dir: Direction,
     meters: u8,
     color: Color,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum D {
-    U,
-    D,
-    L,
-    R,
-}
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 struct Color(u8, u8, u8);
 
-impl D {
-    fn to_ivec2(self) -> IVec2 {
-        match self {
-            D::U => ivec2(0, 1),
-            D::D => ivec2(0, -1),
-            D::L => ivec2(-1, 0),
-            D::R => ivec2(1, 0),
-        }
+impl Color {
+    /// The step hidden in the colour: its first five hex digits are the distance and the last
+    /// one the direction, `0` to `3` for right, down, left and up.
+    fn to_step(self) -> (Direction, i64) {
+        let Color(r, g, b) = self;
+        let distance = (r as i64) << 12 | (g as i64) << 4 | (b as i64) >> 4;
+        let direction =
+            [Direction::E, Direction::S, Direction::W, Direction::N][(b & 0xf) as usize % 4];
+
+        (direction, distance)
     }
 }
 
@@ -57,107 +49,73 @@ U 3 (#a77fa3)
 L 2 (#015232)
 U 2 (#7a21e3)";
 
-pub fn solve() -> (String, String) {
-    let input = _EX.to_string();
-    // let input = read_to_string("inputs/input18.txt").expect("could not read input");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let (_, instructions) = parse_instructions(&input).expect("epic parse fail");
+    let instructions = parse::all(input, parse_instructions).expect("epic parse fail");
 
-    let trench = dig_trench(&instructions);
-    println!("{}", display_field(&trench));
-    let p1 = trench.len();
+    let lagoon = Polygon::from_steps(
+        instructions
+            .iter()
+            .map(|instruction| (instruction.dir, instruction.meters as i64)),
+    );
+    let p1 = lagoon.lattice_points();
 
-    (p1.to_string(), "".to_string())
-}
+    let lagoon = Polygon::from_steps(
+        instructions
+            .iter()
+            .map(|instruction| instruction.color.to_step()),
+    );
+    let p2 = lagoon.lattice_points();
 
-fn dig_trench(instructions: &[Instruction]) -> HashSet<UVec2> {
-    let mut trench = HashSet::new();
-    let mut digger = ivec2(0, 0);
-    for instruction in instructions {
-        for _ in 0..instruction.meters {
-            digger += instruction.dir.to_ivec2();
-            trench.insert(digger);
-        }
-    }
-    let mut trench = normalize_set(&trench);
-    // dbg!(&trench);
-
-    // let mut grid = set_to_grid(&trench);
-    // grid = grid
-    //     .into_iter()
-    //     .map(|row| {
-    //         row.into_iter()
-    //             .chunk_by(|elem| *elem)
-    //             .into_iter()
-    //             .flat_map(|(key, chunk)| {
-    //                 let chunk: Vec<_> = chunk.collect();
-    //                 if key && chunk.len() >= 3 {
-    //                     let mut v = vec![false; chunk.len() - 2];
-    //                     v.insert(0, true);
-    //                     v.push(true);
-
-    //                     v
-    //                 } else {
-    //                     chunk
-    //                 }
-    //             })
-    //             .collect()
-    //     })
-    //     .collect();
-    // trench = grid_to_set(&grid);
-
-    let topmost = trench.iter().map(|pos| pos.y).max().unwrap();
-    let rightmost = trench.iter().map(|pos| pos.x).max().unwrap();
-
-    println!("{}\n", display_field(&trench));
-
-    for y in 0..=topmost {
-        let mut fill = false;
-        let mut last_was_trench = false;
-        for x in 0..=rightmost {
-            let pos = uvec2(x, y);
-            if trench.contains(&pos) {
-                last_was_trench = true;
-                continue;
-            }
-            if last_was_trench {
-                fill = !fill;
-                if fill {
-                    trench.insert(pos);
-                }
-            }
-            last_was_trench = false;
-        }
-    }
+    (p1.to_string(), p2.to_string())
+}
 
-    trench
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_instructions(input))
 }
 
-fn display_field(trench: &HashSet<UVec2>) -> String {
-    let topmost = trench.iter().map(|pos| pos.y).max().unwrap();
-    let rightmost = trench.iter().map(|pos| pos.x).max().unwrap();
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_instructions).expect("epic parse fail");
+
+    serde_json::json!(model)
+}
 
-    // dbg!(topmost, rightmost);
+/// The outline of a random blob, with every edge stretched by the same factor, and the colours
+/// hiding the same outline stretched by a much bigger one.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let cells = (size as i32 + 2).min(63);
+    let corners = super::random_loop(rng, cells);
+    let stretch = rng.range(1..4) as usize;
+    let colour_stretch = rng.range(1..4096) as usize;
 
-    vec![".".repeat((0..=rightmost).count()); (0..=topmost).count()]
+    let instructions: Vec<String> = corners
         .iter()
-        .enumerate()
-        .map(|(y, line)| {
-            line.char_indices()
-                .map(|(x, _)| {
-                    if trench.contains(&uvec2(x as u32, y as u32)) {
-                        "#"
-                    } else {
-                        "."
-                    }
-                })
-                .collect::<String>()
+        .circular_tuple_windows()
+        .map(|(a, b)| match b - a {
+            IVec2 { x: 1, y: 0 } => 'R',
+            IVec2 { x: -1, y: 0 } => 'L',
+            IVec2 { x: 0, y: 1 } => 'D',
+            _ => 'U',
+        })
+        .chunk_by(|dir| *dir)
+        .into_iter()
+        .map(|(dir, steps)| {
+            let steps = steps.count();
+            let meters = steps * stretch;
+            let colour_dir = "RDLU".find(dir).unwrap();
+            format!(
+                "{dir} {meters} (#{:05x}{colour_dir})",
+                steps * colour_stretch
+            )
         })
-        .join("\n")
+        .collect();
+
+    instructions.join("\n")
 }
 
 fn parse_instructions(input: &str) -> IResult<&str, Vec<Instruction>> {
-    separated_list1(line_ending, parse_instruction).parse(input)
+    parse::lines(parse_instruction).parse(input)
 }
 
 fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
@@ -169,17 +127,10 @@ fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
     Ok((input, Instruction { dir, meters, color }))
 }
 
-fn parse_dir(input: &str) -> IResult<&str, D> {
+fn parse_dir(input: &str) -> IResult<&str, Direction> {
     let (input, raw_dir) = one_of("UDLR")(input)?;
-    let dir = match raw_dir {
-        'U' => D::U,
-        'D' => D::D,
-        'L' => D::L,
-        'R' => D::R,
-        _ => unreachable!(),
-    };
-
-    Ok((input, dir))
+
+    Ok((input, Direction::from_char(raw_dir).unwrap()))
 }
 
 fn parse_color(input: &str) -> IResult<&str, (u8, u8, u8)> {
@@ -191,53 +142,9 @@ fn parse_color(input: &str) -> IResult<&str, (u8, u8, u8)> {
 }
 
 fn get_hex_color(input: &str) -> IResult<&str, u8> {
-    map_res(take_while_m_n(2, 2, |ch: char| ch.is_digit(16)), |s| {
-        u8::from_str_radix(s, 16)
-    })
+    map_res(
+        take_while_m_n(2, 2, |ch: char| ch.is_ascii_hexdigit()),
+        |s| u8::from_str_radix(s, 16),
+    )
     .parse(input)
 }
-
-fn normalize_set(set: &HashSet<IVec2>) -> HashSet<UVec2> {
-    if set.is_empty() {
-        return HashSet::new();
-    }
-
-    let leftmost = set.iter().map(|pos| pos.x).min().unwrap();
-    let bottommost = set.iter().map(|pos| pos.y).min().unwrap();
-
-    set.iter()
-        .map(|pos| (pos - ivec2(leftmost, bottommost)).as_uvec2())
-        .collect()
-}
-
-fn set_to_grid(set: &HashSet<UVec2>) -> Vec<Vec<bool>> {
-    if set.is_empty() {
-        return vec![];
-    }
-
-    let width = set.iter().map(|pos| pos.x).max().unwrap() + 1;
-    let height = set.iter().map(|pos| pos.y).max().unwrap() + 1;
-
-    (0..height)
-        .map(|y| {
-            (0..width)
-                .map(move |x| set.contains(&uvec2(x, y)))
-                .collect()
-        })
-        .collect()
-}
-
-fn grid_to_set(grid: &Vec<Vec<bool>>) -> HashSet<UVec2> {
-    if grid.is_empty() {
-        return HashSet::new();
-    }
-
-    grid.iter()
-        .enumerate()
-        .flat_map(|(x, col)| {
-            col.iter()
-                .enumerate()
-                .flat_map(move |(y, b)| b.then_some(uvec2(x as u32, y as u32)))
-        })
-        .collect()
-}
@@ -1,14 +1,23 @@
-use std::{collections::HashMap, fs::read_to_string, ops::Range};
+use std::{collections::HashMap, fmt, ops::Range};
 
 use nom::{
     IResult, Parser,
     bytes::complete::{tag, take_till, take_until},
     character::complete::{self, line_ending, one_of},
-    multi::{separated_list0, separated_list1},
+    multi::separated_list0,
     sequence::separated_pair,
 };
 
-#[derive(Debug, Clone, Copy)]
+use serde::Serialize;
+
+use crate::{
+    explain::{self, Step},
+    hyperbox::{BoxSet, Hyperbox},
+    parse,
+    rng::Rng,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 struct Part {
     x: u16,
     m: u16,
@@ -16,28 +25,23 @@ struct Part {
     s: u16,
 }
 
-#[derive(Debug, Clone)]
-struct PartRanges {
-    x: Range<u16>,
-    m: Range<u16>,
-    a: Range<u16>,
-    s: Range<u16>,
-}
+/// Every combination of ratings in some ranges, one axis per category in `xmas` order.
+type PartBox = Hyperbox<u16, 4>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 struct Workflow<'a> {
     rules: Vec<Rule<'a>>,
     otherwise: Action<'a>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 struct Rule<'a> {
     category: Category,
     range: Range<u16>,
     action: Action<'a>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 enum Category {
     X,
     M,
@@ -45,13 +49,89 @@ enum Category {
     S,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+impl Category {
+    fn axis(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
 enum Action<'a> {
     SendTo(&'a str),
     Accept,
     Reject,
 }
 
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{x={},m={},a={},s={}}}", self.x, self.m, self.a, self.s)
+    }
+}
+
+impl fmt::Display for Workflow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rules: Vec<_> = self.rules.iter().map(Rule::to_string).collect();
+        write!(f, "{{{},{}}}", rules.join(","), self.otherwise)
+    }
+}
+
+impl fmt::Display for Rule<'_> {
+    /// The inverse of `parse_rule`, which turns `>n` into `n+1..4001` and `<n` into `1..n`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let category = match self.category {
+            Category::X => 'x',
+            Category::M => 'm',
+            Category::A => 'a',
+            Category::S => 's',
+        };
+        if self.range.end == 4001 {
+            write!(f, "{category}>{}:{}", self.range.start - 1, self.action)
+        } else {
+            write!(f, "{category}<{}:{}", self.range.end, self.action)
+        }
+    }
+}
+
+impl fmt::Display for Action<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::SendTo(label) => write!(f, "{label}"),
+            Action::Accept => write!(f, "A"),
+            Action::Reject => write!(f, "R"),
+        }
+    }
+}
+
+/// The workflows a part passes through, with the rule that sent it on from each.
+#[derive(Serialize)]
+struct Routing {
+    part: Part,
+    hops: Vec<String>,
+    accepted: bool,
+}
+
+impl fmt::Display for Routing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verdict = if self.accepted {
+            "accepted"
+        } else {
+            "rejected"
+        };
+        write!(f, "{} {verdict}", self.part)?;
+        for hop in &self.hops {
+            write!(f, "\n  {hop}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Step for Routing {
+    fn part(&self) -> Option<u8> {
+        Some(1)
+    }
+}
+
 const _EX: &str = "px{a<2006:qkq,m>2090:A,rfg}
 pv{a>1716:R,A}
 lnx{m>1548:A,A}
@@ -70,50 +150,51 @@ hdj{m>838:A,pv}
 {x=2461,m=1339,a=466,s=291}
 {x=2127,m=1623,a=2188,s=1013}";
 
-pub fn solve() -> (String, String) {
-    let input = _EX.to_string();
-    // let input = read_to_string("inputs/input19.txt").expect("could not read input");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let (_, (workflows, parts)) = parse_input(&input).expect("epic parse fail");
+    let (workflows, parts) = parse::all(input, parse_input).expect("epic parse fail");
     // dbg!(workflows, parts);
 
     let p1: u32 = parts
         .iter()
         .map(|part| {
             let mut action = Action::SendTo("in");
+            // which rule of each workflow matched, or none for its fallback
+            let mut route = vec![];
             'outer: while let Action::SendTo(label) = action {
                 let workflow = workflows.get(label).unwrap();
-                for rule in &workflow.rules {
-                    use Category::{X, M, A, S};
-                    match rule.category {
-                        X => {
-                            if rule.range.contains(&part.x) {
-                                action = rule.action;
-                                continue 'outer;
-                            }
-                        }
-                        M => {
-                            if rule.range.contains(&part.m) {
-                                action = rule.action;
-                                continue 'outer;
-                            }
-                        }
-                        A => {
-                            if rule.range.contains(&part.a) {
-                                action = rule.action;
-                                continue 'outer;
-                            }
-                        }
-                        S => {
-                            if rule.range.contains(&part.s) {
-                                action = rule.action;
-                                continue 'outer;
-                            }
-                        }
+                for (i, rule) in workflow.rules.iter().enumerate() {
+                    use Category::{A, M, S, X};
+                    let rating = match rule.category {
+                        X => part.x,
+                        M => part.m,
+                        A => part.a,
+                        S => part.s,
+                    };
+                    if rule.range.contains(&rating) {
+                        route.push((label, Some(i)));
+                        action = rule.action;
+                        continue 'outer;
                     }
                 }
+                route.push((label, None));
                 action = workflow.otherwise;
             }
+            explain::step(|| Routing {
+                part: *part,
+                hops: route
+                    .iter()
+                    .map(|&(label, rule)| {
+                        let workflow = &workflows[label];
+                        match rule {
+                            Some(i) => format!("{label}: {}", workflow.rules[i]),
+                            None => format!("{label}: otherwise {}", workflow.otherwise),
+                        }
+                    })
+                    .collect(),
+                accepted: action == Action::Accept,
+            });
 
             (action, part)
         })
@@ -126,134 +207,173 @@ pub fn solve() -> (String, String) {
         })
         .sum();
 
-    let accept_workflows = workflows.iter().filter(|(_, workflow)| {
-        workflow
-            .rules
-            .iter()
-            .any(|rule| rule.action == Action::Accept)
-            || workflow.otherwise == Action::Accept
-    });
-
-    let p2 = accept_workflows.flat_map(|(w_label, workflow)| {
-        let part_ranges = PartRanges { x: 1..4001, m: 1..4001, a: 1..4001, s: 1..4001 };
-        let mut part_ranges_collection = vec![];
-        if workflow.otherwise == Action::Accept {
-            let range_opposites = workflow
-                    .rules
-                    .iter()
-                    .map(|rule| (rule.category, range_opposites(&rule.range)));
-                let new_part_ranges_iter = range_opposites
-                    .flat_map(|(category, ranges)|
-                        ranges.iter().map(|range| new_part_ranges(&part_ranges, category, range)).collect::<Vec<_>>()
-                    );
-                
-                part_ranges_collection.append(&mut new_part_ranges_iter
-                    .flat_map(|new_part_ranges| find_accepted_ranges(&new_part_ranges, &workflows, w_label)).collect::<Vec<_>>());
-        }
-        for Rule { category, range, action} in &workflow.rules {
-            if action == &Action::Accept {
-                part_ranges_collection.append(&mut find_accepted_ranges(&new_part_ranges(&part_ranges, *category, range), &workflows, w_label));
-            }
-        }
+    let p2 = accepted_parts(&workflows).volume();
 
-        part_ranges_collection
-    })
-    .map(|PartRanges {x, m, a, s}| 
-        [x, m, a, s].iter().map(|range| range.len()).product::<usize>()
-    )
-    .fold(0u128, |acc, x| acc + (x as u128));
+    (p1.to_string(), p2.to_string())
+}
+
+/// Same answers as `solve`, working out part 1 by looking each part up in the boxes of
+/// accepted parts from part 2 instead of sending it through the workflows.
+pub fn solve_boxes(input: &str) -> (String, String) {
+    let (workflows, parts) = parse::all(input, parse_input).expect("epic parse fail");
+    let accepted = accepted_parts(&workflows);
 
-    (p1.to_string(), "".to_string())
+    let p1: u32 = parts
+        .iter()
+        .map(|part| [part.x, part.m, part.a, part.s])
+        .filter(|ratings| accepted.contains(ratings))
+        .map(|ratings| ratings.iter().map(|&n| n as u32).sum::<u32>())
+        .sum();
+    let p2 = accepted.volume();
+
+    (p1.to_string(), p2.to_string())
 }
 
-fn intersect_ranges(a: &Range<u16>, b: &Range<u16>) -> Range<u16> {
-    (a.start.max(b.start))..(a.end.max(b.end))
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_input(input))
 }
 
-fn range_opposites(range: &Range<u16>) -> [Range<u16>; 2] {
-    [1..range.start, range.end..4001]
+pub fn model(input: &str) -> serde_json::Value {
+    let (workflows, parts) = parse::all(input, parse_input).expect("epic parse fail");
+
+    serde_json::json!({ "workflows": workflows, "parts": parts })
 }
 
-fn find_accepted_ranges(part_ranges: &PartRanges, workflows: &HashMap<&str, Workflow>, label: &str) -> Vec<PartRanges> {
-    if label == "in" {
-        return vec![part_ranges.clone()];
+/// Workflows that only ever send parts to workflows listed after them in a hidden order
+/// starting at `in`, so every part ends up accepted or rejected.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut names = vec!["in".to_string()];
+    while names.len() < 2 * size + 2 {
+        let name: String = (0..rng.range(2..4))
+            .map(|_| (b'a' + rng.below(26) as u8) as char)
+            .collect();
+        if !names.contains(&name) {
+            names.push(name);
+        }
     }
 
-    let applicable_workflows = workflows
+    let mut workflows: Vec<String> = names
         .iter()
-        .filter(|(_, workflow)| workflow
-            .rules
-            .iter()
-            .any(|rule| rule.action == Action::SendTo(label))
-            || workflow.otherwise == Action::SendTo(label)
-        );
-
-    applicable_workflows
-        .flat_map(|(w_label, workflow)| {
-            let mut part_ranges_collection = vec![];
-            if workflow.otherwise == Action::SendTo(label) {
-                let range_opposites = workflow
-                    .rules
-                    .iter()
-                    .map(|rule| (rule.category, range_opposites(&rule.range)));
-                let new_part_ranges_iter = range_opposites
-                    .flat_map(|(category, ranges)|
-                        ranges.iter().map(|range| new_part_ranges(part_ranges, category, range)).collect::<Vec<_>>()
-                    );
-                
-                part_ranges_collection.append(&mut new_part_ranges_iter
-                    .flat_map(|new_part_ranges| find_accepted_ranges(&new_part_ranges, workflows, w_label)).collect::<Vec<_>>());
-            }
-            for Rule { category, range, action} in &workflow.rules {
-                if action == &Action::SendTo(label) {
-                    part_ranges_collection.append(&mut find_accepted_ranges(&new_part_ranges(part_ranges, *category, range), workflows, w_label));
+        .enumerate()
+        .map(|(i, name)| {
+            let target = |rng: &mut Rng| {
+                let later = names.len() - i - 1;
+                match rng.below(later + 2) {
+                    0 => "A",
+                    1 => "R",
+                    n => names[i + n - 1].as_str(),
                 }
-            }
+            };
+            let rules: Vec<String> = (0..rng.range(1..4))
+                .map(|_| {
+                    let category = *rng.choose(&['x', 'm', 'a', 's']);
+                    let comparison = *rng.choose(&['<', '>']);
+                    let num = rng.range(1..4001);
+                    format!("{category}{comparison}{num}:{}", target(rng))
+                })
+                .collect();
+            let otherwise = target(rng);
+            format!("{name}{{{},{otherwise}}}", rules.join(","))
+        })
+        .collect();
+    rng.shuffle(&mut workflows);
 
-            part_ranges_collection
+    let parts: Vec<String> = (0..5 * size)
+        .map(|_| {
+            let [x, m, a, s] = [(); 4].map(|_| rng.range(1..4001));
+            format!("{{x={x},m={m},a={a},s={s}}}")
         })
-        .collect()
+        .collect();
+
+    format!("{}\n\n{}", workflows.join("\n"), parts.join("\n"))
 }
 
-fn new_part_ranges(part_ranges: &PartRanges, category: Category, range: &Range<u16>) -> PartRanges {
-    use Category::{X, M, A, S};
-    match category {
-        X => PartRanges {
-            x: intersect_ranges(&part_ranges.x, range),
-            ..part_ranges.clone()
-        },
-        M => PartRanges {
-            m: intersect_ranges(&part_ranges.m, range),
-            ..part_ranges.clone()
-        },
-        A => PartRanges {
-            a: intersect_ranges(&part_ranges.a, range),
-            ..part_ranges.clone()
-        },
-        S => PartRanges {
-            s: intersect_ranges(&part_ranges.s, range),
-            ..part_ranges.clone()
-        },
+pub fn round_trip(input: &str) -> Result<(), String> {
+    let (workflows, parts) = parse::all(input, parse_input).expect("epic parse fail");
+    let printed = print_input(&workflows, &parts);
+
+    super::check_round_trip(&(workflows, parts), &printed, parse_input(&printed))
+}
+
+/// Workflows come out sorted by name, since the map doesn't remember their order.
+fn print_input(workflows: &HashMap<&str, Workflow>, parts: &[Part]) -> String {
+    let mut labels: Vec<_> = workflows.keys().collect();
+    labels.sort();
+    let workflows: Vec<_> = labels
+        .into_iter()
+        .map(|label| format!("{label}{}", workflows[label]))
+        .collect();
+    let parts: Vec<_> = parts.iter().map(Part::to_string).collect();
+
+    format!("{}\n\n{}", workflows.join("\n"), parts.join("\n"))
+}
+
+fn every_part() -> PartBox {
+    Hyperbox::new([(); 4].map(|_| 1..4001))
+}
+
+/// Every combination of ratings the workflows accept.
+fn accepted_parts(workflows: &HashMap<&str, Workflow>) -> BoxSet<u16, 4> {
+    let mut accepted = BoxSet::new();
+    follow(workflows, Action::SendTo("in"), every_part(), &mut accepted);
+
+    accepted
+}
+
+/// Sends every part in `parts` on to wherever `action` says, adding the ones that end up
+/// accepted to `accepted`.
+fn follow(
+    workflows: &HashMap<&str, Workflow>,
+    action: Action,
+    parts: PartBox,
+    accepted: &mut BoxSet<u16, 4>,
+) {
+    if parts.is_empty() {
+        return;
+    }
+    let label = match action {
+        Action::Accept => return accepted.insert(parts),
+        Action::Reject => return,
+        Action::SendTo(label) => label,
+    };
+
+    // the parts each rule doesn't catch fall through to the next
+    let workflow = &workflows[label];
+    let mut rest = BoxSet::new();
+    rest.insert(parts);
+    for rule in &workflow.rules {
+        let caught = every_part().with(rule.category.axis(), rule.range.clone());
+        for parts in rest.iter() {
+            follow(
+                workflows,
+                rule.action,
+                parts.intersection(&caught),
+                accepted,
+            );
+        }
+        rest.subtract(&caught);
+    }
+    for parts in rest.iter() {
+        follow(workflows, workflow.otherwise, parts.clone(), accepted);
     }
 }
 
-fn parse_input(input: &str) -> IResult<&str, (HashMap<&str, Workflow>, Vec<Part>)> {
+fn parse_input(input: &str) -> IResult<&str, (HashMap<&str, Workflow<'_>>, Vec<Part>)> {
     separated_pair(
         parse_workflows,
         (line_ending, line_ending),
-        separated_list1(line_ending, parse_part),
+        parse::lines(parse_part),
     )
     .parse(input)
 }
 
-fn parse_workflows(input: &str) -> IResult<&str, HashMap<&str, Workflow>> {
-    let (input, labels_and_workflows) =
-        separated_list1(line_ending, parse_workflow).parse(input)?;
+fn parse_workflows(input: &str) -> IResult<&str, HashMap<&str, Workflow<'_>>> {
+    let (input, labels_and_workflows) = parse::lines(parse_workflow).parse(input)?;
 
     Ok((input, labels_and_workflows.into_iter().collect()))
 }
 
-fn parse_workflow(input: &str) -> IResult<&str, (&str, Workflow)> {
+fn parse_workflow(input: &str) -> IResult<&str, (&str, Workflow<'_>)> {
     let (input, name) = take_until("{")(input)?;
     let (input, _) = tag("{")(input)?;
     let (input, rules) = separated_list0(tag(","), parse_rule).parse(input)?;
@@ -264,12 +384,12 @@ fn parse_workflow(input: &str) -> IResult<&str, (&str, Workflow)> {
     Ok((input, (name, Workflow { rules, otherwise })))
 }
 
-fn parse_rule(input: &str) -> IResult<&str, Rule> {
+fn parse_rule(input: &str) -> IResult<&str, Rule<'_>> {
     let (input, category) = one_of("xmas")(input)?;
     let (input, comparison_sign) = one_of("><")(input)?;
     let (input, num) = complete::u16(input)?;
 
-    use Category::{X, M, A, S};
+    use Category::{A, M, S, X};
     let category = match category {
         'x' => X,
         'm' => M,
@@ -279,7 +399,7 @@ fn parse_rule(input: &str) -> IResult<&str, Rule> {
     };
 
     let range: Range<u16> = match comparison_sign {
-        '>' => num..4001,
+        '>' => num.saturating_add(1)..4001,
         '<' => 1..num,
         _ => unreachable!(),
     };
@@ -297,7 +417,7 @@ fn parse_rule(input: &str) -> IResult<&str, Rule> {
     ))
 }
 
-fn parse_action(input: &str) -> IResult<&str, Action> {
+fn parse_action(input: &str) -> IResult<&str, Action<'_>> {
     let (input, label) = take_till(|c: char| !c.is_alphabetic()).parse(input)?;
 
     Ok((
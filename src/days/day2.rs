@@ -1,20 +1,20 @@
-use std::{fs::read_to_string, ops::Add};
+use std::{fmt, io::BufRead, ops::Add};
 
 use nom::{
-    IResult, Parser,
-    branch::alt,
-    bytes::complete::tag,
-    character::complete,
-    multi::{separated_list0, separated_list1},
+    IResult, Parser, branch::alt, bytes::complete::tag, character::complete, multi::separated_list0,
 };
 
-#[derive(Debug)]
+use serde::Serialize;
+
+use crate::{parse, rng::Rng};
+
+#[derive(Debug, PartialEq, Serialize)]
 struct Game {
     game_number: u32,
     cube_sets: Vec<CubeSet>,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
 struct CubeSet {
     red: u32,
     green: u32,
@@ -33,6 +33,29 @@ impl Add<CubeSet> for CubeSet {
     }
 }
 
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cube_sets: Vec<_> = self.cube_sets.iter().map(CubeSet::to_string).collect();
+        write!(f, "Game {}: {}", self.game_number, cube_sets.join("; "))
+    }
+}
+
+impl fmt::Display for CubeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // a colour that wasn't drawn reads back as 0 anyway
+        let counts: Vec<_> = [
+            (self.red, "red"),
+            (self.green, "green"),
+            (self.blue, "blue"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, color)| format!("{count} {color}"))
+        .collect();
+        write!(f, "{}", counts.join(", "))
+    }
+}
+
 #[derive(Debug)]
 enum Color {
     Red,
@@ -40,45 +63,89 @@ enum Color {
     Green,
 }
 
-pub fn solve() -> (String, String) {
-    let input = read_to_string("inputs/input2.txt").expect("input could not be read");
+pub fn solve(input: &str) -> (String, String) {
+    let (p1, p2) = super::sum_lines(input, game_values);
 
-    let (_, games) = parse_games(&input).expect("could not parse");
-    let p1: u32 = games
-        .iter()
-        .filter(|game| {
-            game.cube_sets
-                .iter()
-                .all(|CubeSet { red, green, blue }| *red <= 12 && *green <= 13 && *blue <= 14)
-        })
-        .map(|game| game.game_number)
-        .sum();
+    (p1.to_string(), p2.to_string())
+}
+
+pub fn solve_stream(input: &mut dyn BufRead) -> (String, String) {
+    let (p1, p2) = super::sum_line_iter(super::stream_lines(input), game_values);
+
+    (p1.to_string(), p2.to_string())
+}
 
-    let p2: u32 = games
+/// The game's number if it was possible, and the power of the fewest cubes it needed.
+fn game_values(line: &str) -> (u32, u32) {
+    let game = parse::all(line, parse_game).expect("could not parse");
+    let possible = game
+        .cube_sets
         .iter()
-        .map(|game| {
-            game.cube_sets
-                .iter()
-                .fold(CubeSet::default(), |acc, n| CubeSet {
-                    red: acc.red.max(n.red),
-                    green: acc.green.max(n.green),
-                    blue: acc.blue.max(n.blue),
+        .all(|CubeSet { red, green, blue }| *red <= 12 && *green <= 13 && *blue <= 14);
+    let CubeSet { red, green, blue } =
+        game.cube_sets
+            .iter()
+            .fold(CubeSet::default(), |acc, n| CubeSet {
+                red: acc.red.max(n.red),
+                green: acc.green.max(n.green),
+                blue: acc.blue.max(n.blue),
+            });
+
+    (
+        if possible { game.game_number } else { 0 },
+        red * green * blue,
+    )
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_games(input))
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_games).expect("could not parse");
+
+    serde_json::json!(model)
+}
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let games: Vec<String> = (1..=10 * size)
+        .map(|game_number| {
+            let cube_sets: Vec<String> = (0..rng.range(1..6))
+                .map(|_| {
+                    let mut colors = ["red", "green", "blue"];
+                    rng.shuffle(&mut colors);
+                    let counts: Vec<String> = colors[..rng.range(1..4) as usize]
+                        .iter()
+                        .map(|color| format!("{} {color}", rng.range(1..21)))
+                        .collect();
+                    counts.join(", ")
                 })
+                .collect();
+            format!("Game {game_number}: {}", cube_sets.join("; "))
         })
-        .map(|CubeSet { red, green, blue }| red * green * blue)
-        .sum();
+        .collect();
 
-    (p1.to_string(), p2.to_string())
+    games.join("\n")
+}
+
+pub fn round_trip(input: &str) -> Result<(), String> {
+    let games = parse::all(input, parse_games).expect("could not parse");
+    let printed = print_games(&games);
+
+    super::check_round_trip(&games, &printed, parse_games(&printed))
+}
+
+fn print_games(games: &[Game]) -> String {
+    let games: Vec<_> = games.iter().map(Game::to_string).collect();
+    games.join("\n")
 }
 
 fn parse_games(input: &str) -> IResult<&str, Vec<Game>> {
-    separated_list1(complete::line_ending, parse_game).parse(input)
+    parse::lines(parse_game).parse(input)
 }
 
 fn parse_game(input: &str) -> IResult<&str, Game> {
-    let (input, _) = tag("Game ")(input)?;
-    let (input, game_number) = complete::u32(input)?;
-    let (input, _) = tag(": ")(input)?;
+    let (input, game_number) = parse::header("Game", complete::u32).parse(input)?;
     let (input, cube_sets) = parse_cube_sets(input)?;
 
     Ok((
@@ -1,5 +1,8 @@
-use regex::Regex;
-use std::{collections::HashMap, fs::read_to_string};
+use std::collections::{HashMap, HashSet};
+
+use glam::{IVec2, ivec2};
+
+use crate::{grid::Grid, rng::Rng};
 
 const _EX: &str = r#"467..114..
 ...*......
@@ -12,72 +15,26 @@ const _EX: &str = r#"467..114..
 ...$.*....
 .664.598.."#;
 
-pub fn solve() -> (String, String) {
-    // let input = EX.to_string();
-    let input = read_to_string("inputs/input3.txt")
-        .expect("input not readable")
-        .replace("\r\n", "\n");
-    let line_len = input.lines().next().unwrap().len();
-    let input = ".".repeat(line_len) + "\n" + &input + &".".repeat(line_len);
-    let input: String = input
-        .lines()
-        .map(|line| ".".to_string() + line + ".\n")
-        .collect();
-    let line_len = input.lines().next().unwrap().len() + 1;
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
+    let schematic = Grid::parse(input, |ch| ch);
 
-    let re_num = Regex::new(r"\d+").unwrap();
-    let number_matches: Vec<_> = re_num
-        .captures_iter(&input)
-        .map(|cap| cap.get(0).unwrap())
-        .collect();
     let mut p1 = 0u32;
-    for num in &number_matches {
-        let a = num.start();
-        let b = num.end();
-        let check_string = input[(a - 1 - line_len)..(b + 1 - line_len)].to_string()
-            + &input[(a - 1)..(b + 1)]
-            + &input[(a - 1 + line_len)..(b + 1 + line_len)];
-        if check_string.chars().any(is_special_character) {
-            p1 += num.as_str().parse::<u32>().unwrap();
-        }
-    }
-
-    let mut star_matches: HashMap<usize, Vec<u32>> = HashMap::new();
-    for num in &number_matches {
-        let a = num.start();
-        let b = num.end();
-
-        for i in (a - 1 - line_len)..(b + 1 - line_len) {
-            if input.chars().nth(i) == Some('*') {
-                let parsed_num = num.as_str().parse().unwrap();
-                star_matches
-                    .entry(i)
-                    .and_modify(|v| {
-                        v.push(parsed_num);
-                    })
-                    .or_insert(vec![parsed_num]);
-            }
-        }
-        for i in (a - 1)..(b + 1) {
-            if input.chars().nth(i) == Some('*') {
-                let parsed_num = num.as_str().parse().unwrap();
-                star_matches
-                    .entry(i)
-                    .and_modify(|v| {
-                        v.push(parsed_num);
-                    })
-                    .or_insert(vec![parsed_num]);
-            }
+    let mut star_matches: HashMap<IVec2, Vec<u32>> = HashMap::new();
+    for (number, positions) in part_numbers(&schematic) {
+        let adjacent: HashSet<IVec2> = positions
+            .iter()
+            .flat_map(|pos| schematic.neighbours8(*pos))
+            .collect();
+        if adjacent
+            .iter()
+            .any(|pos| is_special_character(schematic[*pos]))
+        {
+            p1 += number;
         }
-        for i in (a - 1 + line_len)..(b + 1 + line_len) {
-            if input.chars().nth(i) == Some('*') {
-                let parsed_num = num.as_str().parse().unwrap();
-                star_matches
-                    .entry(i)
-                    .and_modify(|v| {
-                        v.push(parsed_num);
-                    })
-                    .or_insert(vec![parsed_num]);
+        for pos in adjacent {
+            if schematic[pos] == '*' {
+                star_matches.entry(pos).or_default().push(number);
             }
         }
     }
@@ -90,6 +47,78 @@ pub fn solve() -> (String, String) {
     (p1.to_string(), p2.to_string())
 }
 
+/// Every run of digits in the schematic, with the positions it covers.
+fn part_numbers(schematic: &Grid<char>) -> Vec<(u32, Vec<IVec2>)> {
+    let mut numbers = vec![];
+    for (y, row) in schematic.rows().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            let len = row[x..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+            if len == 0 {
+                x += 1;
+                continue;
+            }
+            let number = row[x..x + len].iter().collect::<String>().parse().unwrap();
+            let positions = (x..x + len).map(|x| ivec2(x as i32, y as i32)).collect();
+            numbers.push((number, positions));
+            x += len;
+        }
+    }
+
+    numbers
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let schematic = Grid::parse(input, |ch| ch);
+    let numbers: Vec<_> = part_numbers(&schematic)
+        .into_iter()
+        .map(|(number, positions)| serde_json::json!({ "number": number, "positions": positions }))
+        .collect();
+    let symbols: Vec<_> = schematic
+        .iter()
+        .filter(|(_, ch)| is_special_character(**ch))
+        .map(|(pos, ch)| serde_json::json!({ "symbol": ch, "pos": pos }))
+        .collect();
+
+    serde_json::json!({ "numbers": numbers, "symbols": symbols })
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    if !input.chars().any(|ch| ch.is_ascii_digit()) || !input.chars().any(is_special_character) {
+        return 0;
+    }
+
+    super::grid_len(input, |ch| {
+        ch.is_ascii_digit() || ch == '.' || is_special_character(ch)
+    })
+}
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let width = 10 * size;
+    let rows: Vec<String> = (0..width)
+        .map(|_| {
+            let mut row = String::new();
+            while row.len() < width {
+                let len = rng.range(1..4) as usize;
+                if rng.chance(0.15) && row.len() + len < width {
+                    // the trailing dot keeps numbers on one row from running together
+                    row += &rng
+                        .range(10i64.pow(len as u32 - 1)..10i64.pow(len as u32))
+                        .to_string();
+                    row.push('.');
+                } else if rng.chance(0.1) {
+                    row.push(*rng.choose(&['*', '#', '+', '$', '/', '@', '=', '%', '&', '-']));
+                } else {
+                    row.push('.');
+                }
+            }
+            row
+        })
+        .collect();
+
+    rows.join("\n")
+}
+
 fn is_special_character(ch: char) -> bool {
     !ch.is_alphanumeric() && ch != '.' && !ch.is_whitespace()
 }
@@ -1,23 +1,89 @@
 use nom::{
     IResult, Parser,
     bytes::complete::tag,
-    character::complete::{self, line_ending, space1},
-    multi::separated_list1,
+    character::complete::{self, space1},
     sequence::separated_pair,
 };
 use std::{
-    collections::{HashMap, HashSet},
-    fs::read_to_string,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    io::BufRead,
 };
 
-#[derive(Debug)]
+use serde::Serialize;
+
+use crate::{
+    explain::{self, Step},
+    parse,
+    rng::Rng,
+};
+
+#[derive(Debug, PartialEq, Serialize)]
 struct LottoCard {
+    #[serde(serialize_with = "super::serialize_sorted")]
     winning_numbers: HashSet<u32>,
+    #[serde(serialize_with = "super::serialize_sorted")]
     card_numbers: HashSet<u32>,
 }
 
 type LottoDeck = HashMap<usize, LottoCard>;
 
+impl fmt::Display for LottoCard {
+    /// Numbers in ascending order, each padded to two columns like the puzzle's.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let aligned = |numbers: &HashSet<u32>| {
+            let mut numbers: Vec<_> = numbers.iter().collect();
+            numbers.sort();
+            let numbers: Vec<_> = numbers.iter().map(|n| format!("{n:>2}")).collect();
+            numbers.join(" ")
+        };
+        write!(
+            f,
+            "{} | {}",
+            aligned(&self.winning_numbers),
+            aligned(&self.card_numbers)
+        )
+    }
+}
+
+/// How many copies of a card part 2 ends up with, and which cards each of them wins.
+#[derive(Serialize)]
+struct CardCopies {
+    id: usize,
+    copies: usize,
+    matches: usize,
+}
+
+impl fmt::Display for CardCopies {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            id,
+            copies,
+            matches,
+        } = self;
+        let copies = match copies {
+            1 => "1 copy".to_string(),
+            _ => format!("{copies} copies"),
+        };
+        match matches {
+            0 => write!(f, "card {id}: {copies}, no matches"),
+            1 => write!(f, "card {id}: {copies}, each winning card {}", id + 1),
+            _ => write!(
+                f,
+                "card {id}: {copies}, each winning cards {} to {}",
+                id + 1,
+                id + matches
+            ),
+        }
+    }
+}
+
+impl Step for CardCopies {
+    fn part(&self) -> Option<u8> {
+        Some(2)
+    }
+}
+
 const _EX: &str = r"Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
 Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
 Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
@@ -25,48 +91,124 @@ Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input4.txt").expect("input not readable");
-    let (_, cards) = parse_deck(&input).expect("parsing fail");
-
-    let p1: u32 = cards
-        .values()
-        .map(|card| &card.winning_numbers & &card.card_numbers)
-        .filter(|intersection| !intersection.is_empty())
-        .map(|intersection| 2u32.pow(intersection.len() as u32 - 1))
-        .sum();
-
-    let mut card_counts = vec![1usize; cards.len()];
-    card_counts.insert(0, 0);
-
-    for id in 1..card_counts.len() {
-        let won_cards = process_card(id, &cards);
-        for i in 1..=won_cards {
-            card_counts[id + i] += card_counts[id];
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
+    solve_lines(input.lines())
+}
+
+pub fn solve_stream(input: &mut dyn BufRead) -> (String, String) {
+    solve_lines(super::stream_lines(input))
+}
+
+/// Cards only win copies of the few cards right after them, so part 2 only has to hold on
+/// to the copies still waiting for those.
+fn solve_lines<L: AsRef<str>>(lines: impl Iterator<Item = L>) -> (String, String) {
+    let mut p1 = 0u32;
+    let mut p2 = 0usize;
+    let mut pending_copies: VecDeque<usize> = VecDeque::new();
+
+    for line in lines {
+        let (id, card) = parse::all(line.as_ref(), parse_card).expect("parsing fail");
+        let won_cards = process_card(&card);
+        if won_cards > 0 {
+            p1 += 2u32.pow(won_cards as u32 - 1);
         }
-    }
 
-    let p2: usize = card_counts.iter().sum();
+        let copies = 1 + pending_copies.pop_front().unwrap_or(0);
+        p2 += copies;
+        explain::step(|| CardCopies {
+            id,
+            copies,
+            matches: won_cards,
+        });
+        if pending_copies.len() < won_cards {
+            pending_copies.resize(won_cards, 0);
+        }
+        for pending in pending_copies.iter_mut().take(won_cards) {
+            *pending += copies;
+        }
+    }
 
     (p1.to_string(), p2.to_string())
 }
 
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_deck(input))
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_deck).expect("parsing fail");
+
+    serde_json::json!(model)
+}
+
+/// Cards whose matches never win copies of cards past the end of the table.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let card_count = 5 * size + 1;
+    let id_width = card_count.to_string().len();
+    let aligned = |numbers: &[u32]| {
+        numbers
+            .iter()
+            .map(|n| format!("{n:>2}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let cards: Vec<String> = (1..=card_count)
+        .map(|id| {
+            let mut numbers: Vec<u32> = (1..100).collect();
+            rng.shuffle(&mut numbers);
+            let (winning, rest) = numbers.split_at(5);
+            let matches = rng.below(winning.len().min(card_count - id) + 1);
+            let mut card_numbers: Vec<u32> = winning[..matches]
+                .iter()
+                .chain(&rest[..8 - matches])
+                .copied()
+                .collect();
+            rng.shuffle(&mut card_numbers);
+            format!(
+                "Card {id:>id_width$}: {} | {}",
+                aligned(winning),
+                aligned(&card_numbers)
+            )
+        })
+        .collect();
+
+    cards.join("\n")
+}
+
+pub fn round_trip(input: &str) -> Result<(), String> {
+    let deck = parse::all(input, parse_deck).expect("parsing fail");
+    let printed = print_deck(&deck);
+
+    super::check_round_trip(&deck, &printed, parse_deck(&printed))
+}
+
+fn print_deck(deck: &LottoDeck) -> String {
+    let id_width = deck.keys().max().map_or(1, |id| id.to_string().len());
+    let mut ids: Vec<_> = deck.keys().collect();
+    ids.sort();
+    let cards: Vec<_> = ids
+        .into_iter()
+        .map(|id| format!("Card {id:>id_width$}: {}", deck[id]))
+        .collect();
+
+    cards.join("\n")
+}
+
 fn parse_deck(input: &str) -> IResult<&str, LottoDeck> {
-    let (_, ids_and_cards) = separated_list1(line_ending, parse_card).parse(input)?;
+    let (input, ids_and_cards) = parse::lines(parse_card).parse(input)?;
     let deck: LottoDeck = ids_and_cards.into_iter().collect();
 
     Ok((input, deck))
 }
 
 fn parse_card(input: &str) -> IResult<&str, (usize, LottoCard)> {
-    let (input, _) = (tag("Card"), space1).parse(input)?;
-    let (input, card_id) = complete::usize(input)?;
-    let (input, _) = (tag(":"), space1).parse(input)?;
+    let (input, card_id) = parse::header("Card", complete::usize).parse(input)?;
     let (input, (winning_numbers, card_numbers)) = separated_pair(
-        separated_list1(space1, complete::u32),
+        parse::numbers(complete::u32),
         (tag(" |"), space1),
-        separated_list1(space1, complete::u32),
+        parse::numbers(complete::u32),
     )
     .parse(input)?;
 
@@ -85,10 +227,6 @@ fn parse_card(input: &str) -> IResult<&str, (usize, LottoCard)> {
     ))
 }
 
-fn process_card(id: usize, deck: &LottoDeck) -> usize {
-    if let Some(card) = deck.get(&id) {
-        (&card.winning_numbers & &card.card_numbers).len()
-    } else {
-        0
-    }
+fn process_card(card: &LottoCard) -> usize {
+    (&card.winning_numbers & &card.card_numbers).len()
 }
@@ -1,111 +1,93 @@
-use std::{fs::read_to_string, ops::Range};
+use std::fmt;
 
 use itertools::Itertools;
 use nom::{
     IResult, Parser,
     bytes::complete::{tag, take_until},
     character::complete::{self, line_ending, space1},
-    multi::separated_list1,
-    sequence::pair,
 };
 
-#[derive(Debug)]
+use serde::Serialize;
+
+use crate::{
+    explain::{self, Step},
+    interval::IntervalSet,
+    parse,
+    rng::Rng,
+};
+
+#[derive(Debug, PartialEq, Serialize)]
 struct Almanac {
     seeds: Vec<i64>,
     maps: Vec<AlmanacMap>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize)]
 struct AlmanacMap {
     entries: Vec<AlmanacEntry>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 struct AlmanacEntry {
     source_start: i64,
     dest_start: i64,
     len: i64,
 }
 
-#[derive(Debug, Clone, Default)]
-struct RangeSet(Vec<Range<i64>>);
-
-impl RangeSet {
-    fn union(&self, other: &Self) -> Self {
-        match (self.0.is_empty(), other.0.is_empty()) {
-            (true, true) => RangeSet::default(),
-            (true, false) => other.clone(),
-            (false, true) => self.clone(),
-            _ => RangeSet(merge_range_sets(self.0.clone(), other.0.clone())),
+impl fmt::Display for Almanac {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "seeds: {}", self.seeds.iter().join(" "))?;
+        for (i, map) in self.maps.iter().enumerate() {
+            // the parser skips the names, so any beyond the usual seven are made up
+            let name = MAP_NAMES.get(i).copied().unwrap_or("unknown-to-unknown");
+            write!(f, "\n\n{name} map:\n{}", map.entries.iter().join("\n"))?;
         }
-    }
 
-    fn intersection(&self, other: &Self) -> Self {
-        let mut result = vec![];
-        for r1 in &self.0 {
-            for r2 in &other.0 {
-                if r1.start < r2.end && r1.end > r2.start {
-                    let start = r1.start.max(r2.start);
-                    let end = r1.end.min(r2.end);
-                    result.push(start..end);
-                }
-            }
-        }
+        Ok(())
+    }
+}
 
-        RangeSet(result)
+impl fmt::Display for AlmanacEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.dest_start, self.source_start, self.len)
     }
+}
 
-    fn difference(&self, other: &Self) -> Self {
-        let mut result = self.0.clone();
-        for other_range in &other.0 {
-            let mut new_result = vec![];
-            for range in &result {
-                if range.start >= other_range.end || range.end <= other_range.start {
-                    new_result.push(range.clone());
-                } else {
-                    if range.start < other_range.start {
-                        new_result.push(range.start..other_range.start);
-                    }
-                    if range.end > other_range.end {
-                        new_result.push(other_range.end..range.end);
-                    }
-                }
-            }
-            result = new_result;
+/// What a part 1 seed turns into after each map.
+#[derive(Serialize)]
+struct SeedChain(Vec<i64>);
+
+impl fmt::Display for SeedChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "seed {}", self.0[0])?;
+        for (i, value) in self.0[1..].iter().enumerate() {
+            let category = MAP_NAMES
+                .get(i)
+                .and_then(|name| name.split("-to-").nth(1))
+                .unwrap_or("unknown");
+            write!(f, " -> {category} {value}")?;
         }
 
-        RangeSet(result)
-    }
-
-    fn get_first(&self) -> Option<i64> {
-        self.0.first().map(|r| r.start)
+        Ok(())
     }
 }
 
-fn merge_range_sets(a: Vec<Range<i64>>, b: Vec<Range<i64>>) -> Vec<Range<i64>> {
-    if a.is_empty() && b.is_empty() {
-        return vec![];
+impl Step for SeedChain {
+    fn part(&self) -> Option<u8> {
+        Some(1)
     }
-
-    let mut combination = a;
-    combination.extend(b);
-    combination.sort_by(|a, b| a.start.cmp(&b.start));
-
-    let mut result = Vec::new();
-    let mut current = combination[0].clone();
-    for range in combination[1..].iter() {
-        if current.end < range.start {
-            result.push(current);
-            current = (*range).clone();
-        } else {
-            current.end = current.end.max(range.end);
-        }
-    }
-    result.push(current);
-
-    result
 }
 
+const MAP_NAMES: [&str; 7] = [
+    "seed-to-soil",
+    "soil-to-fertilizer",
+    "fertilizer-to-water",
+    "water-to-light",
+    "light-to-temperature",
+    "temperature-to-humidity",
+    "humidity-to-location",
+];
+
 const _EX: &str = r"seeds: 79 14 55 13
 
 seed-to-soil map:
@@ -140,29 +122,35 @@ humidity-to-location map:
 60 56 37
 56 93 4";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input5.txt").expect("input not readable");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let (_, Almanac { seeds, maps }) = parse_almanac(&input).expect("epic parse fail");
+    let Almanac { seeds, maps } = parse::all(input, parse_almanac).expect("epic parse fail");
 
     let mut seeds_p1 = seeds.clone();
     process_seeds(&mut seeds_p1, &maps);
 
     let p1 = seeds_p1.iter().min().unwrap();
+    for &seed in &seeds {
+        explain::step(|| {
+            let mut chain = vec![seed];
+            for map in &maps {
+                let mut value = [*chain.last().unwrap()];
+                process_seeds(&mut value, std::slice::from_ref(map));
+                chain.push(value[0]);
+            }
+            SeedChain(chain)
+        });
+    }
 
-    let seeds_p2: Vec<_> = seeds
+    let mut location_ranges: IntervalSet<i64> = seeds
         .iter()
         .tuples()
-        .map(|(&start, &range)| (start..(start + range)))
+        .map(|(&start, &range)| start..(start + range))
         .collect();
+    let mut ranges_to_add = IntervalSet::new();
 
-    let mut location_ranges = RangeSet(merge_range_sets(vec![], seeds_p2));
-    let mut ranges_to_add = RangeSet(vec![]);
-
-    #[allow(clippy::single_range_in_vec_init)]
     for map in maps {
-        dbg!(&location_ranges);
         for AlmanacEntry {
             dest_start,
             source_start,
@@ -170,39 +158,95 @@ pub fn solve() -> (String, String) {
         } in map.entries
         {
             let mapping_difference = dest_start - source_start;
-            let mapping_range = RangeSet(vec![source_start..(source_start + len)]);
+            let mapping_range = IntervalSet::from(source_start..(source_start + len));
             let range_diff = mapping_range.intersection(&location_ranges);
-            for diff in &range_diff.0 {
-                ranges_to_add = ranges_to_add.union(&RangeSet(vec![
-                    (diff.start + mapping_difference)..(diff.end + mapping_difference),
-                ]))
-            }
+            ranges_to_add = ranges_to_add.union(&range_diff.shift(mapping_difference as i128));
             location_ranges = location_ranges.difference(&range_diff);
         }
         location_ranges = location_ranges.union(&ranges_to_add);
-        ranges_to_add = RangeSet(vec![]);
+        ranges_to_add = IntervalSet::new();
     }
-    location_ranges = location_ranges.union(&ranges_to_add);
 
-    dbg!(&location_ranges);
+    let p2 = location_ranges.first().unwrap();
 
-    let p2 = location_ranges.get_first().unwrap();
+    (p1.to_string(), p2.to_string())
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_almanac(input))
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_almanac).expect("parse fail");
+
+    serde_json::json!(model)
+}
+
+/// Deliberately naive reference for differential testing: part 2 pushes every seed in
+/// every range through the maps one at a time instead of using `IntervalSet`.
+pub fn solve_naive(input: &str) -> (String, String) {
+    let Almanac { seeds, maps } = parse::all(input, parse_almanac).expect("epic parse fail");
+
+    let p1 = seeds
+        .iter()
+        .map(|&seed| map_seed(seed, &maps))
+        .min()
+        .unwrap();
+
+    // one seed at a time, since the ranges can hold far too many to keep around
+    let p2 = seeds
+        .iter()
+        .tuples()
+        .flat_map(|(&start, &range)| start..(start + range))
+        .map(|seed| map_seed(seed, &maps))
+        .min()
+        .unwrap();
 
     (p1.to_string(), p2.to_string())
 }
 
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let max = 20 * size as i64;
+    let seeds: Vec<_> = (0..size)
+        .flat_map(|_| [rng.range(0..max), rng.range(1..max / 2 + 2)])
+        .map(|n| n.to_string())
+        .collect();
+
+    let maps: Vec<_> = MAP_NAMES
+        .iter()
+        .map(|name| {
+            let entries: Vec<_> = (0..rng.range(1..size as i64 + 2))
+                .map(|_| {
+                    let (dest_start, source_start) = (rng.range(0..max), rng.range(0..max));
+                    format!("{dest_start} {source_start} {}", rng.range(1..max / 2 + 2))
+                })
+                .collect();
+            format!("{name} map:\n{}", entries.join("\n"))
+        })
+        .collect();
+
+    format!("seeds: {}\n\n{}", seeds.join(" "), maps.join("\n\n"))
+}
+
+pub fn round_trip(input: &str) -> Result<(), String> {
+    let almanac = parse::all(input, parse_almanac).expect("parse fail");
+    let printed = almanac.to_string();
+
+    super::check_round_trip(&almanac, &printed, parse_almanac(&printed))
+}
+
 fn parse_almanac(input: &str) -> IResult<&str, Almanac> {
-    let (input, _) = tag("seeds: ")(input)?;
-    let (input, seeds) = separated_list1(space1, complete::i64).parse(input)?;
-    let (input, _) = pair(line_ending, line_ending).parse(input)?;
-    let (input, maps) = separated_list1(pair(line_ending, line_ending), parse_map).parse(input)?;
+    let (input, _) = parse::label("seeds").parse(input)?;
+    let (input, seeds) = parse::numbers(complete::i64).parse(input)?;
+    let (input, _) = (line_ending, line_ending).parse(input)?;
+    let (input, maps) = parse::blocks(parse_map).parse(input)?;
 
     Ok((input, Almanac { seeds, maps }))
 }
 
 fn parse_map(input: &str) -> IResult<&str, AlmanacMap> {
     let (input, _) = (take_until("map:"), tag("map:"), line_ending).parse(input)?;
-    let (input, entries) = separated_list1(line_ending, parse_entry).parse(input)?;
+    let (input, entries) = parse::lines(parse_entry).parse(input)?;
 
     Ok((input, AlmanacMap { entries }))
 }
@@ -222,15 +266,21 @@ fn parse_entry(input: &str) -> IResult<&str, AlmanacEntry> {
 }
 
 fn process_seeds(seeds: &mut [i64], maps: &[AlmanacMap]) {
+    for seed in seeds.iter_mut() {
+        *seed = map_seed(*seed, maps);
+    }
+}
+
+fn map_seed(mut seed: i64, maps: &[AlmanacMap]) -> i64 {
     for map in maps {
-        for seed in seeds.iter_mut() {
-            for entry in &map.entries {
-                let difference = entry.dest_start - entry.source_start;
-                if ((entry.source_start)..(entry.source_start + entry.len)).contains(seed) {
-                    *seed += difference;
-                    break;
-                }
+        for entry in &map.entries {
+            let difference = entry.dest_start - entry.source_start;
+            if ((entry.source_start)..(entry.source_start + entry.len)).contains(&seed) {
+                seed += difference;
+                break;
             }
         }
     }
+
+    seed
 }
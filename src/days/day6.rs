@@ -1,13 +1,13 @@
-use std::fs::read_to_string;
-
 use nom::{
     IResult, Parser,
-    bytes::complete::tag,
-    character::complete::{self, line_ending, space1},
-    multi::separated_list1,
+    character::complete::{self, line_ending},
 };
 
-#[derive(Debug)]
+use serde::Serialize;
+
+use crate::{math, parse, rng::Rng};
+
+#[derive(Debug, Serialize)]
 struct Race {
     time: u64,
     distance: u64,
@@ -16,11 +16,10 @@ struct Race {
 const _EX: &str = r"Time:      7  15   30
 Distance:  9  40  200";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input6.txt").expect("could not read file");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let (_, races) = parse_races(&input).expect("epic parse fail");
+    let races = parse::all(input, parse_races).expect("epic parse fail");
 
     let p1: u32 = races
         .iter()
@@ -52,11 +51,144 @@ pub fn solve() -> (String, String) {
     (p1.to_string(), p2.to_string())
 }
 
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_races(input))
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_races).expect("epic parse fail");
+
+    serde_json::json!(model)
+}
+
+/// Deliberately naive reference for differential testing: part 2 counts every winning
+/// hold time instead of using the first win and the race's symmetry.
+pub fn solve_naive(input: &str) -> (String, String) {
+    let races = parse::all(input, parse_races).expect("epic parse fail");
+
+    let p1: u64 = races
+        .iter()
+        .map(|race| count_wins(race.time, race.distance))
+        .product();
+
+    let time_p2: u64 = races
+        .iter()
+        .map(|race| race.time.to_string())
+        .collect::<String>()
+        .parse()
+        .unwrap();
+    let distance_p2: u64 = races
+        .iter()
+        .map(|race| race.distance.to_string())
+        .collect::<String>()
+        .parse()
+        .unwrap();
+    let p2 = count_wins(time_p2, distance_p2);
+
+    (p1.to_string(), p2.to_string())
+}
+
+fn count_wins(time: u64, distance: u64) -> u64 {
+    (0..=time).filter(|i| i * (time - i) > distance).count() as u64
+}
+
+/// The most digits the times may join up into. It keeps part 2's race, and every
+/// `i * (time - i)` in it, well inside a `u64`, and the naive solver's scan short, the way the
+/// real input's eight digits do.
+const MAX_JOINED_DIGITS: usize = 8;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let max_time = (10 * size as i64 + 10).min(10i64.pow(MAX_JOINED_DIGITS as u32));
+    loop {
+        let mut races = vec![];
+        let mut digits = 0;
+        while races.len() < size.max(1) {
+            let time = rng.range(1..max_time) as u64;
+            digits += time.to_string().len();
+            if digits > MAX_JOINED_DIGITS {
+                break;
+            }
+            let best = (time / 2) * (time - time / 2);
+            let distance = rng.range(0..best.max(1) as i64) as u64;
+            races.push(Race { time, distance });
+        }
+
+        // the joined race has to be winnable too, and the parts almost always are
+        let joined = |value: fn(&Race) -> u64| -> Option<u64> {
+            races
+                .iter()
+                .map(|race| value(race).to_string())
+                .collect::<String>()
+                .parse()
+                .ok()
+        };
+        let (Some(time), Some(distance)) = (joined(|race| race.time), joined(|race| race.distance))
+        else {
+            continue;
+        };
+        if closed_form_wins(time, distance) > 0
+            && races
+                .iter()
+                .all(|race| closed_form_wins(race.time, race.distance) > 0)
+        {
+            return print_races(&races);
+        }
+    }
+}
+
+/// How many hold times beat `distance`: the ones strictly between the roots of
+/// `i * (time - i) = distance`, at `(time ± sqrt(time² - 4 * distance)) / 2`.
+fn closed_form_wins(time: u64, distance: u64) -> u64 {
+    let Some(discriminant) = (time * time).checked_sub(4 * distance) else {
+        return 0;
+    };
+    // rounding the root down can only put this at or before the first win
+    let mut first = (time - math::isqrt(discriminant)) / 2;
+    while first <= time / 2 && first * (time - first) <= distance {
+        first += 1;
+    }
+
+    if first > time / 2 {
+        0
+    } else {
+        time - 2 * first + 1
+    }
+}
+
+fn print_races(races: &[Race]) -> String {
+    let widths: Vec<_> = races
+        .iter()
+        .map(|race| {
+            race.time
+                .to_string()
+                .len()
+                .max(race.distance.to_string().len())
+        })
+        .collect();
+    let row = |label: &str, values: Vec<u64>| {
+        let columns: String = values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("  {value:>width$}"))
+            .collect();
+        format!("{label:<9}{columns}")
+    };
+
+    format!(
+        "{}\n{}",
+        row("Time:", races.iter().map(|race| race.time).collect()),
+        row(
+            "Distance:",
+            races.iter().map(|race| race.distance).collect()
+        )
+    )
+}
+
 fn parse_races(input: &str) -> IResult<&str, Vec<Race>> {
-    let (input, _) = (tag("Time:"), space1).parse(input)?;
-    let (input, times) = separated_list1(space1, complete::u64).parse(input)?;
-    let (input, _) = (line_ending, tag("Distance:"), space1).parse(input)?;
-    let (input, distances) = separated_list1(space1, complete::u64).parse(input)?;
+    let (input, _) = parse::label("Time").parse(input)?;
+    let (input, times) = parse::numbers(complete::u64).parse(input)?;
+    let (input, _) = (line_ending, parse::label("Distance")).parse(input)?;
+    let (input, distances) = parse::numbers(complete::u64).parse(input)?;
 
     Ok((
         input,
@@ -67,3 +199,21 @@ fn parse_races(input: &str) -> IResult<&str, Vec<Race>> {
             .collect(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_form_wins_matches_counting() {
+        for time in 0..60 {
+            for distance in 0..time * time / 4 + 3 {
+                assert_eq!(
+                    closed_form_wins(time, distance),
+                    count_wins(time, distance),
+                    "time {time}, distance {distance}"
+                );
+            }
+        }
+    }
+}
@@ -1,19 +1,30 @@
 use nom::{
     IResult, Parser,
-    bytes::complete::take,
-    character::complete::{self, line_ending, space1},
-    multi::{count, separated_list1},
+    character::complete::{self, one_of, space1},
+    multi::count,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    io::BufRead,
+};
+
+use serde::Serialize;
+
+use crate::{
+    explain::{self, Step},
+    parse,
+    rng::Rng,
 };
-use std::{collections::HashMap, fs::read_to_string};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Serialize)]
 struct Play {
     hand: [u8; 5],
     bid: u64,
     hand_type: HandType,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize)]
 enum HandType {
     HighCard,
     Pair,
@@ -24,17 +35,99 @@ enum HandType {
     FiveOfAKind,
 }
 
+impl fmt::Display for Play {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hand: String = self
+            .hand
+            .iter()
+            .map(|card| match card {
+                // jokers are 0 once part 2 has demoted them
+                0 | 11 => 'J',
+                2..=9 => (b'0' + card) as char,
+                10 => 'T',
+                12 => 'Q',
+                13 => 'K',
+                14 => 'A',
+                _ => unreachable!(),
+            })
+            .collect();
+        write!(f, "{hand} {}", self.bid)
+    }
+}
+
+/// A hand's place in the final ordering and what it wins there.
+#[derive(Serialize)]
+struct RankedHand {
+    part: u8,
+    rank: usize,
+    play: Play,
+}
+
+impl fmt::Display for RankedHand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>4}. {} {:?}, wins {}",
+            self.rank,
+            self.play,
+            self.play.hand_type,
+            self.rank as u64 * self.play.bid
+        )
+    }
+}
+
+impl Step for RankedHand {
+    fn part(&self) -> Option<u8> {
+        Some(self.part)
+    }
+}
+
+/// Bids per distinct hand, in rank order.
+#[derive(Default)]
+struct HandTally(BTreeMap<(HandType, [u8; 5]), DealtHand>);
+
+#[derive(Default)]
+struct DealtHand {
+    count: u64,
+    bids: u64,
+    /// Equal hands rank in the order they were dealt, like the stable sort in `solve`, so
+    /// each bid also earns one extra for every copy of the hand dealt before it.
+    extra: u64,
+}
+
+impl HandTally {
+    fn add(&mut self, play: &Play) {
+        let dealt = self.0.entry((play.hand_type, play.hand)).or_default();
+        dealt.extra += dealt.count * play.bid;
+        dealt.count += 1;
+        dealt.bids += play.bid;
+    }
+
+    fn winnings(&self) -> u64 {
+        let mut ranked = 0;
+        self.0
+            .values()
+            .map(|dealt| {
+                let winnings = (ranked + 1) * dealt.bids + dealt.extra;
+                ranked += dealt.count;
+                winnings
+            })
+            .sum()
+    }
+}
+
 const _EX: &str = r"32T3K 765
 T55J5 684
 KK677 28
 KTJJT 220
 QQQJA 483";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input7.txt").expect("input could not be read");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
 
-    let (_, plays) = parse_plays(&input).expect("epic parse fail");
+    let plays = super::map_lines(input, |line| {
+        parse::all(line, parse_play).expect("epic parse fail")
+    });
     let mut plays_p1 = plays.clone();
     plays_p1.sort_by(|a, b| {
         if a.hand_type == b.hand_type {
@@ -43,25 +136,20 @@ pub fn solve() -> (String, String) {
             a.hand_type.cmp(&b.hand_type)
         }
     });
+    for (i, play) in plays_p1.iter().enumerate() {
+        explain::step(|| RankedHand {
+            part: 1,
+            rank: i + 1,
+            play: *play,
+        });
+    }
     let p1: u64 = plays_p1
         .iter()
         .enumerate()
         .map(|(i, Play { bid, .. })| (i + 1) as u64 * bid)
         .sum();
 
-    let mut plays_p2: Vec<_> = plays
-        .clone()
-        .iter()
-        .map(|&Play { hand, bid, .. }| {
-            let hand = hand.map(|card| if card == 11 { 0 } else { card });
-            let hand_type = get_hand_type(&hand);
-            Play {
-                hand,
-                bid,
-                hand_type,
-            }
-        })
-        .collect();
+    let mut plays_p2: Vec<_> = plays.clone().iter().map(with_jokers).collect();
     plays_p2.sort_by(|a, b| {
         if a.hand_type == b.hand_type {
             a.hand.cmp(&b.hand)
@@ -69,6 +157,13 @@ pub fn solve() -> (String, String) {
             a.hand_type.cmp(&b.hand_type)
         }
     });
+    for (i, play) in plays_p2.iter().enumerate() {
+        explain::step(|| RankedHand {
+            part: 2,
+            rank: i + 1,
+            play: *play,
+        });
+    }
     let p2: u64 = plays_p2
         .iter()
         .enumerate()
@@ -78,32 +173,92 @@ pub fn solve() -> (String, String) {
     (p1.to_string(), p2.to_string())
 }
 
+/// Ranking needs every hand, but there are only so many different hands, so streaming
+/// keeps a tally per hand instead of the hands themselves.
+pub fn solve_stream(input: &mut dyn BufRead) -> (String, String) {
+    let mut tally_p1 = HandTally::default();
+    let mut tally_p2 = HandTally::default();
+    for line in super::stream_lines(input) {
+        let play = parse::all(&line, parse_play).expect("epic parse fail");
+        tally_p1.add(&play);
+        tally_p2.add(&with_jokers(&play));
+    }
+
+    (
+        tally_p1.winnings().to_string(),
+        tally_p2.winnings().to_string(),
+    )
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_plays(input))
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_plays).expect("epic parse fail");
+
+    serde_json::json!(model)
+}
+
+/// Distinct hands drawn from a few ranks each, so every hand type turns up.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let cards = [
+        '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
+    ];
+    let mut hands: Vec<String> = vec![];
+    while hands.len() < 10 * size {
+        let ranks: Vec<char> = (0..rng.range(1..6)).map(|_| *rng.choose(&cards)).collect();
+        let hand: String = (0..5).map(|_| *rng.choose(&ranks)).collect();
+        if !hands.contains(&hand) {
+            hands.push(hand);
+        }
+    }
+
+    let plays: Vec<String> = hands
+        .iter()
+        .map(|hand| format!("{hand} {}", rng.range(1..1001)))
+        .collect();
+    plays.join("\n")
+}
+
+pub fn round_trip(input: &str) -> Result<(), String> {
+    let plays = parse::all(input, parse_plays).expect("epic parse fail");
+    let printed = print_plays(&plays);
+
+    super::check_round_trip(&plays, &printed, parse_plays(&printed))
+}
+
+fn print_plays(plays: &[Play]) -> String {
+    let plays: Vec<_> = plays.iter().map(Play::to_string).collect();
+    plays.join("\n")
+}
+
 fn parse_plays(input: &str) -> IResult<&str, Vec<Play>> {
-    separated_list1(line_ending, parse_play).parse(input)
+    parse::lines(parse_play).parse(input)
 }
 
 fn parse_play(input: &str) -> IResult<&str, Play> {
-    let (input, hand) = count(take(1usize), 5).parse(input)?;
+    let (input, hand) = count(one_of("23456789TJQKA"), 5).parse(input)?;
     let (input, _) = space1(input)?;
     let (input, bid) = complete::u64(input)?;
 
     let hand: [u8; 5] = hand
         .into_iter()
         .take(5)
-        .map(|s| match s {
-            "2" => 2u8,
-            "3" => 3,
-            "4" => 4,
-            "5" => 5,
-            "6" => 6,
-            "7" => 7,
-            "8" => 8,
-            "9" => 9,
-            "T" => 10,
-            "J" => 11,
-            "Q" => 12,
-            "K" => 13,
-            "A" => 14,
+        .map(|ch| match ch {
+            '2' => 2u8,
+            '3' => 3,
+            '4' => 4,
+            '5' => 5,
+            '6' => 6,
+            '7' => 7,
+            '8' => 8,
+            '9' => 9,
+            'T' => 10,
+            'J' => 11,
+            'Q' => 12,
+            'K' => 13,
+            'A' => 14,
             _ => unreachable!(),
         })
         .collect::<Vec<_>>()
@@ -121,6 +276,17 @@ fn parse_play(input: &str) -> IResult<&str, Play> {
     ))
 }
 
+/// The same play with its jacks turned into jokers for part 2.
+fn with_jokers(&Play { hand, bid, .. }: &Play) -> Play {
+    let hand = hand.map(|card| if card == 11 { 0 } else { card });
+    let hand_type = get_hand_type(&hand);
+    Play {
+        hand,
+        bid,
+        hand_type,
+    }
+}
+
 fn get_hand_type(hand: &[u8; 5]) -> HandType {
     let mut card_counts = HashMap::new();
     for card in hand {
@@ -1,19 +1,25 @@
-use std::{collections::HashMap, fs::read_to_string};
+use std::{collections::HashMap, fmt};
+
+use itertools::Itertools;
 
 use nom::{
     IResult, Parser,
     bytes::complete::{tag, take},
     character::complete::line_ending,
-    multi::{many_till, separated_list1},
     sequence::separated_pair,
 };
 
-#[derive(Debug, Clone, Copy)]
+use serde::Serialize;
+
+use crate::{math, parse, rng::Rng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 enum Direction {
     Left,
     Right,
 }
 
+#[derive(PartialEq, Serialize)]
 struct Path<'a> {
     left: &'a str,
     right: &'a str,
@@ -21,6 +27,12 @@ struct Path<'a> {
 
 type Network<'a> = HashMap<&'a str, Path<'a>>;
 
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.left, self.right)
+    }
+}
+
 const _EX: &str = r"RL
 
 AAA = (BBB, CCC)
@@ -48,11 +60,10 @@ const _EX3: &str = r"LR
 22Z = (22B, 22B)
 XXX = (XXX, XXX)";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX3.to_string();
-    let input = read_to_string("inputs/input8.txt").expect("could not read input");
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX3;
 
-    let (_, (directions, network)) = parse_instructions(&input).expect("epic parse fail");
+    let (directions, network) = parse::all(input, parse_instructions).expect("epic parse fail");
 
     let mut p1 = 0u32;
     let mut current_node = "AAA";
@@ -71,55 +82,181 @@ pub fn solve() -> (String, String) {
         .filter(|node| node.ends_with("A"))
         .cloned()
         .collect();
-    let path_lengths: Vec<_> = starting_nodes
+    let cycles: Vec<_> = starting_nodes
         .iter()
-        .map(|node| {
-            let mut count = 0u64;
-            let mut node = *node;
-            let mut directions_cycle = directions.iter().cycle();
-            while !node.ends_with("Z") {
-                let path = network.get(node).expect("node not in network");
-                node = match directions_cycle.next().unwrap() {
-                    Direction::Left => path.left,
-                    Direction::Right => path.right,
-                };
-                count += 1;
-            }
+        .map(|node| ghost_cycle(&directions, &network, node))
+        .collect();
+    let p2 = first_meeting(&cycles).expect("ghosts never line up");
 
-            count
-        })
+    (p1.to_string(), p2.to_string())
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    super::consumed(input, parse_instructions(input))
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let (directions, network) = parse::all(input, parse_instructions).expect("parse fail");
+
+    serde_json::json!({ "directions": directions, "network": network })
+}
+
+/// Deliberately naive reference for differential testing: part 2 walks every ghost in
+/// lockstep until they all sit on a `Z` node at once, so it doesn't rely on each ghost's
+/// first `Z` also being the length of its cycle.
+pub fn solve_naive(input: &str) -> (String, String) {
+    let (directions, network) = parse::all(input, parse_instructions).expect("epic parse fail");
+
+    let p1 = walk_in_lockstep(
+        &directions,
+        &network,
+        &["AAA"],
+        |node| node == "ZZZ",
+        u64::MAX,
+    )
+    .expect("AAA never reaches ZZZ");
+
+    let starting_nodes: Vec<_> = network
+        .keys()
+        .filter(|node| node.ends_with("A"))
+        .cloned()
         .collect();
-    let p2 = lcm(&path_lengths);
+    let p2 = walk_in_lockstep(
+        &directions,
+        &network,
+        &starting_nodes,
+        |node| node.ends_with("Z"),
+        u64::MAX,
+    )
+    .expect("ghosts never line up");
 
     (p1.to_string(), p2.to_string())
 }
 
-fn parse_instructions(input: &str) -> IResult<&str, (Vec<Direction>, Network)> {
-    separated_pair(parse_directions, line_ending, parse_network).parse(input)
+/// Steps every node in `starts` together until all of them are `done`, giving up after
+/// `limit` steps.
+fn walk_in_lockstep(
+    directions: &[Direction],
+    network: &Network,
+    starts: &[&str],
+    done: impl Fn(&str) -> bool,
+    limit: u64,
+) -> Option<u64> {
+    let mut nodes = starts.to_vec();
+    let mut directions_cycle = directions.iter().cycle();
+    for count in 0..limit {
+        if nodes.iter().all(|node| done(node)) {
+            return Some(count);
+        }
+        let direction = directions_cycle.next().unwrap();
+        for node in &mut nodes {
+            let path = network.get(node).expect("node not in network");
+            *node = match direction {
+                Direction::Left => path.left,
+                Direction::Right => path.right,
+            };
+        }
+    }
+
+    None
 }
 
-fn parse_directions(input: &str) -> IResult<&str, Vec<Direction>> {
-    let (input, (directions, _)) = many_till(take(1usize), line_ending).parse(input)?;
-    let directions: Vec<_> = directions
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    loop {
+        let mut names = vec!["AAA".to_string(), "ZZZ".to_string()];
+        while names.len() < 2 * size + 4 {
+            let suffix = *rng.choose(&['A', 'Z', 'B', 'C', 'D']);
+            let name: String = (0..2)
+                .map(|_| (b'B' + rng.below(24) as u8) as char)
+                .chain([suffix])
+                .collect();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        let directions: String = (0..rng.range(1..size as i64 + 3))
+            .map(|_| *rng.choose(&['L', 'R']))
+            .collect();
+        let paths: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let left = rng.choose(&names);
+                let right = rng.choose(&names);
+                format!("{name} = ({left}, {right})")
+            })
+            .collect();
+        let text = format!("{directions}\n\n{}", paths.join("\n"));
+
+        // only keep networks where both parts finish, otherwise neither solver would
+        let (_, (directions, network)) = parse_instructions(&text).unwrap();
+        let starting_nodes: Vec<_> = names
+            .iter()
+            .map(String::as_str)
+            .filter(|node| node.ends_with("A"))
+            .collect();
+        let limit = 100_000;
+        let p1 = walk_in_lockstep(&directions, &network, &["AAA"], |node| node == "ZZZ", limit);
+        let p2 = walk_in_lockstep(
+            &directions,
+            &network,
+            &starting_nodes,
+            |node| node.ends_with("Z"),
+            limit,
+        );
+        if p1.is_some() && p2.is_some() {
+            return text;
+        }
+    }
+}
+
+pub fn round_trip(input: &str) -> Result<(), String> {
+    let instructions = parse::all(input, parse_instructions).expect("parse fail");
+    let printed = print_instructions(&instructions);
+
+    super::check_round_trip(&instructions, &printed, parse_instructions(&printed))
+}
+
+/// Nodes come out sorted by name, since the network doesn't remember their order.
+fn print_instructions((directions, network): &(Vec<Direction>, Network)) -> String {
+    let directions: String = directions
         .iter()
-        .map(|ch| match *ch {
-            "L" => Direction::Left,
-            "R" => Direction::Right,
-            _ => unreachable!(),
+        .map(|direction| match direction {
+            Direction::Left => 'L',
+            Direction::Right => 'R',
         })
         .collect();
+    let mut nodes: Vec<_> = network.keys().collect();
+    nodes.sort();
+    let paths: Vec<_> = nodes
+        .into_iter()
+        .map(|node| format!("{node} = {}", network[node]))
+        .collect();
 
-    Ok((input, directions))
+    format!("{directions}\n\n{}", paths.join("\n"))
 }
 
-fn parse_network(input: &str) -> IResult<&str, Network> {
-    let (input, paths) = separated_list1(line_ending, parse_path).parse(input)?;
+fn parse_instructions(input: &str) -> IResult<&str, (Vec<Direction>, Network<'_>)> {
+    separated_pair(parse_directions, (line_ending, line_ending), parse_network).parse(input)
+}
+
+fn parse_directions(input: &str) -> IResult<&str, Vec<Direction>> {
+    parse::cells(|ch| match ch {
+        'L' => Some(Direction::Left),
+        'R' => Some(Direction::Right),
+        _ => None,
+    })
+    .parse(input)
+}
+
+fn parse_network(input: &str) -> IResult<&str, Network<'_>> {
+    let (input, paths) = parse::lines(parse_path).parse(input)?;
     let network: Network = paths.into_iter().collect();
 
     Ok((input, network))
 }
 
-fn parse_path(input: &str) -> IResult<&str, (&str, Path)> {
+fn parse_path(input: &str) -> IResult<&str, (&str, Path<'_>)> {
     let (input, start) = take(3usize)(input)?;
     let (input, _) = tag(" = (")(input)?;
     let (input, left) = take(3usize)(input)?;
@@ -130,20 +267,100 @@ fn parse_path(input: &str) -> IResult<&str, (&str, Path)> {
     Ok((input, (start, Path { left, right })))
 }
 
-fn gcf(a: u64, b: u64) -> u64 {
-    if b == 0 {
-        return a;
+/// Where one ghost's walk starts going round in circles, and the steps along the way that
+/// leave it on a `Z` node.
+struct GhostCycle {
+    /// Steps taken before it first reaches the state it keeps coming back to.
+    start: u64,
+    length: u64,
+    /// Every step count below `start + length` that leaves it on a `Z` node.
+    z_steps: Vec<u64>,
+}
+
+impl GhostCycle {
+    fn on_z(&self, steps: u64) -> bool {
+        let steps = if steps < self.start {
+            steps
+        } else {
+            self.start + (steps - self.start) % self.length
+        };
+
+        self.z_steps.contains(&steps)
+    }
+}
+
+fn ghost_cycle(directions: &[Direction], network: &Network, start: &str) -> GhostCycle {
+    // the ghost is in the same state whenever it's on the same node at the same point in the
+    // directions
+    let mut seen = HashMap::new();
+    let mut z_steps = vec![];
+    let mut node = start;
+    let mut steps = 0u64;
+    loop {
+        let state = (node, steps as usize % directions.len());
+        if let Some(&first) = seen.get(&state) {
+            return GhostCycle {
+                start: first,
+                length: steps - first,
+                z_steps,
+            };
+        }
+        seen.insert(state, steps);
+        if node.ends_with("Z") {
+            z_steps.push(steps);
+        }
+
+        let path = network.get(node).expect("node not in network");
+        node = match directions[state.1] {
+            Direction::Left => path.left,
+            Direction::Right => path.right,
+        };
+        steps += 1;
     }
-    gcf(b, a % b)
 }
 
-fn lcm(nums: &[u64]) -> u64 {
-    if nums.len() == 1 {
-        return nums[0];
+/// The fewest steps after which every ghost is on a `Z` node at once.
+fn first_meeting(cycles: &[GhostCycle]) -> Option<u64> {
+    // until every ghost is going round, the ghosts can only meet on a step one of them lists
+    let latest_start = cycles.iter().map(|cycle| cycle.start).max()?;
+    let early = cycles
+        .iter()
+        .flat_map(|cycle| &cycle.z_steps)
+        .copied()
+        .filter(|&steps| steps < latest_start)
+        .filter(|&steps| cycles.iter().all(|cycle| cycle.on_z(steps)))
+        .min();
+    if early.is_some() {
+        return early;
     }
 
-    let a = nums[0];
-    let b = lcm(&nums[1..]);
+    // the shape the puzzle inputs have: each ghost's only `Z` node is a whole lap in, so it's
+    // on one every lap and the ghosts meet once every lap length divides the steps
+    if cycles
+        .iter()
+        .all(|cycle| cycle.start <= cycle.length && cycle.z_steps == [cycle.length])
+    {
+        let lengths: Vec<_> = cycles.iter().map(|cycle| cycle.length).collect();
+        return math::lcm_all(&lengths);
+    }
 
-    a * b / gcf(a, b)
+    // after that, each ghost is on a `Z` node every `length` steps from each of the steps
+    // in its cycle, so line one of those up from every ghost
+    cycles
+        .iter()
+        .map(|cycle| {
+            cycle
+                .z_steps
+                .iter()
+                .filter(|&&steps| steps >= cycle.start)
+                .map(|&steps| (steps % cycle.length, cycle.length))
+                .collect::<Vec<_>>()
+        })
+        .multi_cartesian_product()
+        .filter_map(|congruences| {
+            let (steps, modulus) = math::crt(&congruences)?;
+            let behind = latest_start.saturating_sub(steps);
+            steps.checked_add(behind.div_ceil(modulus).checked_mul(modulus)?)
+        })
+        .min()
 }
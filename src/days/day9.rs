@@ -1,64 +1,103 @@
-use std::fs::read_to_string;
+use std::io::BufRead;
 
-use nom::{
-    IResult, Parser,
-    character::complete::{self, line_ending, space1},
-    multi::separated_list1,
-};
+use nom::{IResult, Parser, character::complete};
 
 use itertools::Itertools;
 
+use crate::{parse, rng::Rng};
+
 const _EX: &str = r"0 3 6 9 12 15
 1 3 6 10 15 21
 10 13 16 21 30 45";
 
-pub fn solve() -> (String, String) {
-    // let input = _EX.to_string();
-    let input = read_to_string("inputs/input9.txt").expect("could not read input");
-
-    let (_, initial_sequences) = parse_sequences(&input).expect("epic parse fail");
-
-    let forward_extrapolated: Vec<_> = initial_sequences
-        .iter()
-        .map(|initial_sequence| get_difference_sequences(initial_sequence))
-        .map(|difference_sequences| {
-            let mut difference_sequences = difference_sequences;
-            difference_sequences.last_mut().unwrap().push(0);
-            for i in (1..difference_sequences.len()).rev() {
-                let a = *difference_sequences[i].last().unwrap();
-                let b = *difference_sequences[i - 1].last().unwrap();
-                difference_sequences[i - 1].push(a + b);
-            }
-            // dbg!(&difference_sequences);
-            difference_sequences
+pub fn solve(input: &str) -> (String, String) {
+    // let input = _EX;
+    let (p1, p2) = super::sum_lines(input, sequence_values);
+
+    (p1.to_string(), p2.to_string())
+}
+
+pub fn solve_stream(input: &mut dyn BufRead) -> (String, String) {
+    let (p1, p2) = super::sum_line_iter(super::stream_lines(input), sequence_values);
+
+    (p1.to_string(), p2.to_string())
+}
+
+fn sequence_values(line: &str) -> (i64, i64) {
+    let initial_sequence = parse::all(line, parse_sequence).expect("epic parse fail");
+    extrapolate(&initial_sequence)
+}
+
+pub fn parsed_len(input: &str) -> usize {
+    match parse_sequences(input) {
+        // a column of lone numbers is more likely a grid of digits than a set of sequences
+        Ok((rest, sequences)) if sequences.iter().any(|sequence| sequence.len() > 1) => {
+            input.len() - rest.len()
+        }
+        _ => 0,
+    }
+}
+
+pub fn model(input: &str) -> serde_json::Value {
+    let model = parse::all(input, parse_sequences).expect("epic parse fail");
+
+    serde_json::json!(model)
+}
+
+/// Polynomial sequences, built up from a random constant difference.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let sequences: Vec<String> = (0..5 * size)
+        .map(|_| {
+            let len = rng.range(5..22);
+            let mut differences: Vec<i64> =
+                (0..rng.range(1..7)).map(|_| rng.range(-10..11)).collect();
+            let sequence: Vec<String> = (0..len)
+                .map(|_| {
+                    let value = differences[0];
+                    for i in 0..differences.len() - 1 {
+                        differences[i] += differences[i + 1];
+                    }
+                    value.to_string()
+                })
+                .collect();
+            sequence.join(" ")
         })
         .collect();
-    let p1: i64 = forward_extrapolated
-        .iter()
-        .map(|difference_sequences| *difference_sequences[0].last().unwrap())
-        .sum();
-
-    let backward_extrapolated = forward_extrapolated
-        .into_iter()
-        .map(|difference_sequences| {
-            let mut difference_sequences = difference_sequences;
-            difference_sequences.last_mut().unwrap().insert(0, 0);
-            for i in (1..difference_sequences.len()).rev() {
-                let a = *difference_sequences[i].first().unwrap();
-                let b = *difference_sequences[i - 1].first().unwrap();
-                difference_sequences[i - 1].insert(0, b - a);
-            }
-            difference_sequences
-        });
-    let p2: i64 = backward_extrapolated
-        .map(|difference_sequences| *difference_sequences[0].first().unwrap())
-        .sum();
 
-    (p1.to_string(), p2.to_string())
+    sequences.join("\n")
 }
 
 fn parse_sequences(input: &str) -> IResult<&str, Vec<Vec<i64>>> {
-    separated_list1(line_ending, separated_list1(space1, complete::i64)).parse(input)
+    parse::lines(parse_sequence).parse(input)
+}
+
+fn parse_sequence(input: &str) -> IResult<&str, Vec<i64>> {
+    parse::numbers(complete::i64).parse(input)
+}
+
+/// The values that would come right after and right before the sequence.
+fn extrapolate(initial_sequence: &[i64]) -> (i64, i64) {
+    let mut difference_sequences = get_difference_sequences(initial_sequence);
+
+    difference_sequences.last_mut().unwrap().push(0);
+    for i in (1..difference_sequences.len()).rev() {
+        let a = *difference_sequences[i].last().unwrap();
+        let b = *difference_sequences[i - 1].last().unwrap();
+        difference_sequences[i - 1].push(a + b);
+    }
+    // dbg!(&difference_sequences);
+
+    difference_sequences.last_mut().unwrap().insert(0, 0);
+    for i in (1..difference_sequences.len()).rev() {
+        let a = *difference_sequences[i].first().unwrap();
+        let b = *difference_sequences[i - 1].first().unwrap();
+        difference_sequences[i - 1].insert(0, b - a);
+    }
+
+    (
+        *difference_sequences[0].last().unwrap(),
+        *difference_sequences[0].first().unwrap(),
+    )
 }
 
 fn get_difference_sequences(initial_sequence: &[i64]) -> Vec<Vec<i64>> {
@@ -1,19 +1,445 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::BufRead,
+    iter::Sum,
+    ops::Add,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[cfg(any(feature = "day10", feature = "day18"))]
+use glam::{IVec2, ivec2};
+use nom::IResult;
+use serde::{Serialize, Serializer};
+
+use crate::rng::Rng;
+
+#[cfg(feature = "day1")]
 pub mod day1;
+#[cfg(feature = "day10")]
 pub mod day10;
+#[cfg(feature = "day11")]
 pub mod day11;
+#[cfg(feature = "day12")]
 pub mod day12;
+#[cfg(feature = "day13")]
 pub mod day13;
+#[cfg(feature = "day14")]
 pub mod day14;
+#[cfg(feature = "day15")]
 pub mod day15;
+#[cfg(feature = "day16")]
 pub mod day16;
+#[cfg(feature = "day17")]
+pub mod day17;
+#[cfg(feature = "day18")]
+pub mod day18;
+#[cfg(feature = "day19")]
+pub mod day19;
+#[cfg(feature = "day2")]
 pub mod day2;
+#[cfg(feature = "day3")]
 pub mod day3;
+#[cfg(feature = "day4")]
 pub mod day4;
+#[cfg(feature = "day5")]
 pub mod day5;
+#[cfg(feature = "day6")]
 pub mod day6;
+#[cfg(feature = "day7")]
 pub mod day7;
+#[cfg(feature = "day8")]
 pub mod day8;
+#[cfg(feature = "day9")]
 pub mod day9;
-pub mod day17;
-pub mod day18;
-pub mod day19;
\ No newline at end of file
+
+pub type Solver = fn(&str) -> (String, String);
+pub type Generator = fn(&mut Rng, usize) -> String;
+pub type RoundTrip = fn(&str) -> Result<(), String>;
+pub type Model = fn(&str) -> serde_json::Value;
+pub type Streamer = fn(&mut dyn BufRead) -> (String, String);
+pub type Scaler = fn(&str, usize) -> (String, String);
+
+/// A way of solving a day: one of the solvers here, or someone else's program that is given
+/// the input on stdin.
+#[derive(Clone)]
+pub enum Implementation {
+    Builtin(Solver),
+    External(Vec<String>),
+}
+
+/// Everything the CLI knows how to do with a single day.
+#[derive(Clone, Copy)]
+pub struct Day {
+    pub solve: Solver,
+    /// How many bytes of an input this day's parser accepts, used to guess which day an
+    /// unlabelled input file belongs to.
+    pub parsed_len: fn(&str) -> usize,
+    /// A slow but obviously correct solver to check `solve`'s shortcuts against.
+    pub reference: Option<Solver>,
+    /// Produces a random valid input of roughly the given size.
+    pub generate: Generator,
+    /// Parses an input, prints the model back out and checks that parsing the printed text
+    /// gives the same model, returning that text if it doesn't.
+    pub round_trip: Option<RoundTrip>,
+    /// What the parser made of an input.
+    pub model: Model,
+    /// Solves an input read a line at a time, in memory that doesn't grow with its length.
+    pub stream: Option<Streamer>,
+    /// Other ways of solving the day, by name, kept around to compare against `solve`.
+    pub alternatives: &'static [(&'static str, Solver)],
+    /// Solves an input made `n` times harder along an axis its generator doesn't grow, for
+    /// `scale` to time in place of ever bigger generated inputs.
+    pub scale: Option<Scaler>,
+}
+
+impl Day {
+    /// Every solver for the day by name: `solve` as "default", `reference` as "naive", then
+    /// the alternatives.
+    pub fn implementations(&self) -> Vec<(&'static str, Solver)> {
+        [("default", self.solve)]
+            .into_iter()
+            .chain(self.reference.map(|reference| ("naive", reference)))
+            .chain(self.alternatives.iter().copied())
+            .collect()
+    }
+
+    pub fn implementation(&self, name: &str) -> Option<Solver> {
+        self.implementations()
+            .into_iter()
+            .find_map(|(impl_name, solve)| (impl_name == name).then_some(solve))
+    }
+
+    /// What `--impl all` runs: every implementation but the naive reference, which can take
+    /// far too long on a real input and is only run when asked for by name.
+    pub fn compared_implementations(&self) -> Vec<(&'static str, Solver)> {
+        self.implementations()
+            .into_iter()
+            .filter(|(name, _)| *name != "naive")
+            .collect()
+    }
+}
+
+macro_rules! day {
+    ($day:ident) => {
+        Day {
+            solve: $day::solve,
+            parsed_len: $day::parsed_len,
+            reference: None,
+            generate: $day::generate,
+            round_trip: None,
+            model: $day::model,
+            stream: None,
+            alternatives: &[],
+            scale: None,
+        }
+    };
+}
+
+/// The inputs baked in by the `embed-inputs` feature, for the days that are built.
+#[cfg(feature = "embed-inputs")]
+pub fn embedded_input(day: u8) -> Option<&'static str> {
+    macro_rules! embedded {
+        ($($day:literal: $feature:literal),*) => {
+            match day {
+                $(
+                    #[cfg(feature = $feature)]
+                    $day => Some(include_str!(concat!(
+                        env!("CARGO_MANIFEST_DIR"),
+                        "/inputs/input",
+                        $day,
+                        ".txt"
+                    ))),
+                )*
+                _ => None,
+            }
+        };
+    }
+
+    embedded!(
+        1: "day1", 2: "day2", 3: "day3", 4: "day4", 5: "day5", 6: "day6", 7: "day7", 8: "day8",
+        9: "day9", 10: "day10", 11: "day11", 12: "day12", 13: "day13", 14: "day14",
+        15: "day15", 16: "day16", 17: "day17", 18: "day18", 19: "day19"
+    )
+}
+
+pub fn get(day: u8) -> Option<Day> {
+    let day = match day {
+        #[cfg(feature = "day1")]
+        1 => Day {
+            stream: Some(day1::solve_stream),
+            ..day!(day1)
+        },
+        #[cfg(feature = "day2")]
+        2 => Day {
+            round_trip: Some(day2::round_trip),
+            stream: Some(day2::solve_stream),
+            ..day!(day2)
+        },
+        #[cfg(feature = "day3")]
+        3 => day!(day3),
+        #[cfg(feature = "day4")]
+        4 => Day {
+            round_trip: Some(day4::round_trip),
+            stream: Some(day4::solve_stream),
+            ..day!(day4)
+        },
+        #[cfg(feature = "day5")]
+        5 => Day {
+            reference: Some(day5::solve_naive),
+            round_trip: Some(day5::round_trip),
+            ..day!(day5)
+        },
+        #[cfg(feature = "day6")]
+        6 => Day {
+            reference: Some(day6::solve_naive),
+            ..day!(day6)
+        },
+        #[cfg(feature = "day7")]
+        7 => Day {
+            round_trip: Some(day7::round_trip),
+            stream: Some(day7::solve_stream),
+            ..day!(day7)
+        },
+        #[cfg(feature = "day8")]
+        8 => Day {
+            reference: Some(day8::solve_naive),
+            round_trip: Some(day8::round_trip),
+            ..day!(day8)
+        },
+        #[cfg(feature = "day9")]
+        9 => Day {
+            stream: Some(day9::solve_stream),
+            ..day!(day9)
+        },
+        #[cfg(feature = "day10")]
+        10 => Day {
+            reference: Some(day10::solve_naive),
+            alternatives: &[
+                ("raycast", day10::solve_raycast),
+                ("winding", day10::solve_winding),
+                ("sparse", day10::solve_sparse),
+            ],
+            ..day!(day10)
+        },
+        #[cfg(feature = "day11")]
+        11 => day!(day11),
+        #[cfg(feature = "day12")]
+        12 => Day {
+            round_trip: Some(day12::round_trip),
+            stream: Some(day12::solve_stream),
+            alternatives: &[("table", day12::solve_table)],
+            scale: Some(day12::solve_unfolded),
+            ..day!(day12)
+        },
+        #[cfg(feature = "day13")]
+        13 => Day { ..day!(day13) },
+        #[cfg(feature = "day14")]
+        14 => day!(day14),
+        #[cfg(feature = "day15")]
+        15 => Day { ..day!(day15) },
+        #[cfg(feature = "day16")]
+        16 => Day {
+            alternatives: &[("sparse", day16::solve_sparse)],
+            ..day!(day16)
+        },
+        #[cfg(feature = "day17")]
+        17 => Day {
+            alternatives: &[
+                ("sparse", day17::solve_sparse),
+                ("dijkstra", day17::solve_dijkstra),
+                ("bucket", day17::solve_bucket),
+            ],
+            ..day!(day17)
+        },
+        #[cfg(feature = "day18")]
+        18 => Day { ..day!(day18) },
+        #[cfg(feature = "day19")]
+        19 => Day {
+            round_trip: Some(day19::round_trip),
+            alternatives: &[("boxes", day19::solve_boxes)],
+            ..day!(day19)
+        },
+        _ => return None,
+    };
+
+    Some(day)
+}
+
+/// Every implemented day, in order.
+pub fn all() -> impl Iterator<Item = (u8, Day)> {
+    (1..=25).filter_map(|day| Some((day, get(day)?)))
+}
+
+static PARALLEL: AtomicBool = AtomicBool::new(false);
+
+/// Lets the days with independent lines or pairs share them out over the rayon pool. Only
+/// does anything in a build with the `parallel` feature.
+pub fn set_parallel(enabled: bool) {
+    PARALLEL.store(enabled, Ordering::Relaxed);
+}
+
+#[cfg(feature = "parallel")]
+fn parallel() -> bool {
+    PARALLEL.load(Ordering::Relaxed)
+}
+
+/// `f` of every line, in order, worked out in parallel if that's switched on.
+fn map_lines<T: Send>(input: &str, f: impl Fn(&str) -> T + Sync + Send) -> Vec<T> {
+    #[cfg(feature = "parallel")]
+    if parallel() {
+        use rayon::prelude::*;
+        return input.par_lines().map(f).collect();
+    }
+
+    input.lines().map(f).collect()
+}
+
+/// Adds up what each line contributes to the two answers, in parallel if that's switched
+/// on. Addition doesn't care which order the lines come back in, so the answers don't either.
+fn sum_lines<T: Add<Output = T> + Default + Send>(
+    input: &str,
+    per_line: impl Fn(&str) -> (T, T) + Sync + Send,
+) -> (T, T) {
+    #[cfg(feature = "parallel")]
+    if parallel() {
+        use rayon::prelude::*;
+        let add = |(a1, a2): (T, T), (b1, b2): (T, T)| (a1 + b1, a2 + b2);
+        return input
+            .par_lines()
+            .map(per_line)
+            .reduce(|| (T::default(), T::default()), add);
+    }
+
+    sum_line_iter(input.lines(), per_line)
+}
+
+/// Adds up what each of `lines` contributes to the two answers, one at a time, for inputs
+/// that arrive a line at a time rather than all at once.
+fn sum_line_iter<T: Add<Output = T> + Default, L: AsRef<str>>(
+    lines: impl Iterator<Item = L>,
+    per_line: impl Fn(&str) -> (T, T),
+) -> (T, T) {
+    lines
+        .map(|line| per_line(line.as_ref()))
+        .fold((T::default(), T::default()), |(a1, a2), (b1, b2)| {
+            (a1 + b1, a2 + b2)
+        })
+}
+
+/// Adds up `f` over every unordered pair of `items`, in parallel if that's switched on.
+fn sum_pairs<T: Sync, S: Sum + Send>(items: &[T], f: impl Fn(&T, &T) -> S + Sync + Send) -> S {
+    let from = |i: usize| items[i + 1..].iter().map(|b| f(&items[i], b)).sum::<S>();
+
+    #[cfg(feature = "parallel")]
+    if parallel() {
+        use rayon::prelude::*;
+        return (0..items.len()).into_par_iter().map(from).sum();
+    }
+
+    (0..items.len()).map(from).sum()
+}
+
+/// Bytes of `input` consumed by a nom parser, or 0 if it failed outright.
+fn consumed<O>(input: &str, result: IResult<&str, O>) -> usize {
+    result.map_or(0, |(rest, _)| input.len() - rest.len())
+}
+
+/// Lines of a streamed input. A read error is as fatal as a parse error.
+fn stream_lines(input: &mut dyn BufRead) -> impl Iterator<Item = String> + '_ {
+    input
+        .lines()
+        .map(|line| line.expect("could not read input"))
+}
+
+/// Whether reparsing a model's printed text used all of it and gave back the same model.
+fn check_round_trip<O: PartialEq>(
+    model: &O,
+    printed: &str,
+    reparsed: IResult<&str, O>,
+) -> Result<(), String> {
+    match reparsed {
+        Ok(("", reparsed)) if reparsed == *model => Ok(()),
+        _ => Err(printed.to_string()),
+    }
+}
+
+/// Serialises a set in sorted order, so dumped models don't change from run to run.
+fn serialize_sorted<T: Ord + Serialize, S: Serializer>(
+    set: &HashSet<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut items: Vec<_> = set.iter().collect();
+    items.sort();
+    items.serialize(serializer)
+}
+
+/// Bytes covered by the leading lines of `input` that form a rectangular grid of `valid`
+/// cells.
+fn grid_len(input: &str, valid: impl Fn(char) -> bool) -> usize {
+    let width = input.lines().next().map_or(0, str::len);
+
+    input
+        .split_inclusive('\n')
+        .take_while(|line| {
+            let row = line.trim_end_matches(['\n', '\r']);
+            row.len() == width && width > 0 && row.chars().all(&valid)
+        })
+        .map(str::len)
+        .sum()
+}
+
+/// Corners of the outline of a random blob of cells in a `cells` by `cells` square, in
+/// walking order starting from the top left one, for days whose input is a closed loop.
+/// Consecutive corners are one step apart and the loop never touches itself.
+#[cfg(any(feature = "day10", feature = "day18"))]
+fn random_loop(rng: &mut Rng, cells: i32) -> Vec<IVec2> {
+    let orthogonal = [ivec2(0, -1), ivec2(1, 0), ivec2(0, 1), ivec2(-1, 0)];
+    loop {
+        let mut blob = vec![ivec2(
+            rng.below(cells as usize) as i32,
+            rng.below(cells as usize) as i32,
+        )];
+        for _ in 0..cells * cells / 2 {
+            let next = *rng.choose(&blob) + *rng.choose(&orthogonal);
+            if (0..cells).contains(&next.x) && (0..cells).contains(&next.y) && !blob.contains(&next)
+            {
+                blob.push(next);
+            }
+        }
+
+        // each exposed side of a cell joins two of its corners
+        let mut connections: HashMap<IVec2, Vec<IVec2>> = HashMap::new();
+        for &cell in &blob {
+            for (dir, a, b) in [
+                (ivec2(0, -1), ivec2(0, 0), ivec2(1, 0)),
+                (ivec2(1, 0), ivec2(1, 0), ivec2(1, 1)),
+                (ivec2(0, 1), ivec2(0, 1), ivec2(1, 1)),
+                (ivec2(-1, 0), ivec2(0, 0), ivec2(0, 1)),
+            ] {
+                if !blob.contains(&(cell + dir)) {
+                    let (a, b) = (cell + a, cell + b);
+                    connections.entry(a).or_default().push(b);
+                    connections.entry(b).or_default().push(a);
+                }
+            }
+        }
+        // a corner touched twice is a pinch, and more edges than one walk covers is a hole
+        if connections.values().any(|next| next.len() != 2) {
+            continue;
+        }
+        let first = *connections.keys().min_by_key(|pos| (pos.y, pos.x)).unwrap();
+        let mut corners = vec![first];
+        let mut current = connections[&first][0];
+        while current != first {
+            let previous = *corners.last().unwrap();
+            corners.push(current);
+            current = *connections[&current]
+                .iter()
+                .find(|next| **next != previous)
+                .unwrap();
+        }
+        if corners.len() == connections.len() {
+            return corners;
+        }
+    }
+}
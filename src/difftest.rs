@@ -0,0 +1,56 @@
+use std::panic::{self, AssertUnwindSafe, catch_unwind};
+
+use crate::{days, rng::Rng};
+
+/// Runs each day's default solver side by side with its other implementations on `cases`
+/// random inputs, reporting the first input they disagree on.
+pub fn difftest(only_day: Option<u8>, cases: u64, seed: u64) {
+    // the solvers are expected to fall over now and then, and we report that ourselves
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    for (day, entry) in days::all() {
+        let implementations = entry.implementations();
+        if implementations.len() < 2 {
+            continue;
+        }
+        if only_day.is_some_and(|only_day| only_day != day) {
+            continue;
+        }
+
+        let disagreement = (0..cases).find_map(|case| {
+            let case_seed = seed.wrapping_add(case);
+            let size = 1 + (case % 4) as usize;
+            let input = (entry.generate)(&mut Rng::new(case_seed), size);
+
+            let solutions: Vec<_> = implementations
+                .iter()
+                .map(|(name, solve)| (*name, catch_unwind(AssertUnwindSafe(|| solve(&input))).ok()))
+                .collect();
+            let agree = solutions
+                .iter()
+                .all(|(_, solution)| solution.is_some() && *solution == solutions[0].1);
+            (!agree).then_some((case_seed, size, input, solutions))
+        });
+
+        match disagreement {
+            None => println!("day {day}: {cases} cases agree"),
+            Some((case_seed, size, input, solutions)) => {
+                println!("day {day}: disagreement at seed {case_seed}, size {size}");
+                for (name, solution) in &solutions {
+                    println!("  {name}: {}", describe(solution));
+                }
+                println!("{input}\n");
+            }
+        }
+    }
+
+    panic::set_hook(previous_hook);
+}
+
+fn describe(solution: &Option<(String, String)>) -> String {
+    match solution {
+        Some((p1, p2)) => format!("{p1}, {p2}"),
+        None => "panicked".to_string(),
+    }
+}
@@ -0,0 +1,110 @@
+use glam::{IVec2, ivec2};
+use serde::Serialize;
+
+/// One of the four ways to step on a grid, in screen coordinates: x grows to the east and y
+/// grows to the south, so north is up the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum Direction {
+    N,
+    E,
+    S,
+    W,
+}
+
+impl Direction {
+    /// Clockwise from north.
+    pub const ALL: [Direction; 4] = [Direction::N, Direction::E, Direction::S, Direction::W];
+
+    pub fn to_ivec2(self) -> IVec2 {
+        match self {
+            Direction::N => ivec2(0, -1),
+            Direction::E => ivec2(1, 0),
+            Direction::S => ivec2(0, 1),
+            Direction::W => ivec2(-1, 0),
+        }
+    }
+
+    /// Reads `NESW`, `URDL` or the arrows `^>v<`.
+    pub fn from_char(ch: char) -> Option<Direction> {
+        match ch {
+            'N' | 'U' | '^' => Some(Direction::N),
+            'E' | 'R' | '>' => Some(Direction::E),
+            'S' | 'D' | 'v' => Some(Direction::S),
+            'W' | 'L' | '<' => Some(Direction::W),
+            _ => None,
+        }
+    }
+
+    pub fn turn_left(self) -> Direction {
+        match self {
+            Direction::N => Direction::W,
+            Direction::E => Direction::N,
+            Direction::S => Direction::E,
+            Direction::W => Direction::S,
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        self.turn_left().opposite()
+    }
+
+    pub fn opposite(self) -> Direction {
+        self.turn_left().turn_left()
+    }
+
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Direction::N | Direction::S)
+    }
+}
+
+/// The eight ways to step on a grid, diagonals included, in the same screen coordinates as
+/// `Direction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compass {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Compass {
+    /// Clockwise from north.
+    pub const ALL: [Compass; 8] = [
+        Compass::N,
+        Compass::NE,
+        Compass::E,
+        Compass::SE,
+        Compass::S,
+        Compass::SW,
+        Compass::W,
+        Compass::NW,
+    ];
+
+    pub fn to_ivec2(self) -> IVec2 {
+        match self {
+            Compass::N => ivec2(0, -1),
+            Compass::NE => ivec2(1, -1),
+            Compass::E => ivec2(1, 0),
+            Compass::SE => ivec2(1, 1),
+            Compass::S => ivec2(0, 1),
+            Compass::SW => ivec2(-1, 1),
+            Compass::W => ivec2(-1, 0),
+            Compass::NW => ivec2(-1, -1),
+        }
+    }
+}
+
+impl From<Direction> for Compass {
+    fn from(direction: Direction) -> Compass {
+        match direction {
+            Direction::N => Compass::N,
+            Direction::E => Compass::E,
+            Direction::S => Compass::S,
+            Direction::W => Compass::W,
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fmt,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use serde::Serialize;
+
+use crate::config::Format;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STEPS: Mutex<Vec<Box<dyn Step>>> = Mutex::new(Vec::new());
+
+/// Something a solver wants to show about how it reached its answer: as text, and as its
+/// fields for `--format json`.
+pub trait Step: fmt::Display + ToJson + Send {
+    /// The part the step belongs to, if it's only about one of them.
+    fn part(&self) -> Option<u8> {
+        None
+    }
+}
+
+/// `Serialize` for a boxed `Step`, which can't have `Serialize` itself as a supertrait since
+/// that isn't object safe. Every `Serialize` type gets it.
+pub trait ToJson {
+    fn to_json(&self) -> serde_json::Value;
+}
+
+impl<T: Serialize> ToJson for T {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("step doesn't serialize")
+    }
+}
+
+/// Records a step while explaining. `build` is only called then, so solvers can leave this
+/// in hot loops.
+pub fn step<S: Step + 'static>(build: impl FnOnce() -> S) {
+    if ENABLED.load(Ordering::Relaxed) {
+        STEPS.lock().unwrap().push(Box::new(build()));
+    }
+}
+
+/// Starts recording steps, from any thread, dropping whatever was left over.
+pub fn start() {
+    STEPS.lock().unwrap().clear();
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stops recording and hands back the steps in the order they were emitted.
+pub fn finish() -> Vec<Box<dyn Step>> {
+    ENABLED.store(false, Ordering::Relaxed);
+    std::mem::take(&mut *STEPS.lock().unwrap())
+}
+
+pub fn render(day: u8, steps: &[Box<dyn Step>], format: Format) {
+    match format {
+        Format::Text => {
+            if steps.is_empty() {
+                println!("day {day} has nothing to explain");
+            }
+            for part in [None, Some(1), Some(2)] {
+                let steps: Vec<_> = steps.iter().filter(|step| step.part() == part).collect();
+                if steps.is_empty() {
+                    continue;
+                }
+                if let Some(part) = part {
+                    println!("Part {part}");
+                }
+                for step in steps {
+                    println!("  {}", step.to_string().replace('\n', "\n  "));
+                }
+                println!();
+            }
+        }
+        Format::Json => {
+            let steps: Vec<_> = steps
+                .iter()
+                .map(|step| {
+                    serde_json::json!({
+                        "part": step.part(),
+                        "step": step.to_string(),
+                        "fields": step.to_json(),
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::json!({ "day": day, "steps": steps }));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    io::{Read, Write},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex, mpsc},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to let a killed solver's stderr finish arriving before taking what there is.
+const KILLED_GRACE: Duration = Duration::from_millis(100);
+
+/// What a run of someone else's solver came back with. Whatever it printed to stderr is kept
+/// either way, since that's usually where the explanation for a wrong answer is.
+pub struct ExternalRun {
+    pub solution: Result<(String, String), String>,
+    pub stderr: String,
+}
+
+/// Runs `command` with `input` on its stdin and reads its answers from the `Solution 1: ...`
+/// and `Solution 2: ...` lines of its stdout, the same lines we print. Anything else it
+/// prints is ignored.
+pub fn run_external(command: &[String], input: &str, timeout: Option<Duration>) -> ExternalRun {
+    let (program, args) = command.split_first().expect("external command is empty");
+    let mut child = match Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            return ExternalRun {
+                solution: Err(format!("could not start {program}: {e}")),
+                stderr: String::new(),
+            };
+        }
+    };
+
+    // feed and drain the pipes on their own threads, so a chatty solver can't fill one up and
+    // stall waiting for us
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let writer = thread::spawn(move || {
+        // a solver that exits without reading everything closes the pipe early, which is fine
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let stdout = Drain::start(child.stdout.take().unwrap());
+    let stderr = Drain::start(child.stderr.take().unwrap());
+
+    let Some(status) = wait(&mut child, timeout) else {
+        // anything it started may still hold the pipes open, so only wait a moment for the
+        // rest of stderr, which is where it'll have said what it was stuck on
+        return ExternalRun {
+            solution: Err(format!("timed out after {:?}", timeout.unwrap())),
+            stderr: stderr.collect(Some(KILLED_GRACE)),
+        };
+    };
+    let _ = writer.join();
+    let stdout = stdout.collect(None);
+    let stderr = stderr.collect(None);
+
+    let solution = if status.success() {
+        parse_answers(&stdout)
+    } else {
+        Err(format!("exited with {status}"))
+    };
+
+    ExternalRun { solution, stderr }
+}
+
+/// A pipe read to the end on its own thread, keeping what it has read so far where it can be
+/// had before the end comes.
+struct Drain {
+    bytes: Arc<Mutex<Vec<u8>>>,
+    finished: mpsc::Receiver<()>,
+}
+
+impl Drain {
+    fn start(mut pipe: impl Read + Send + 'static) -> Drain {
+        let bytes = Arc::new(Mutex::new(Vec::new()));
+        let (tx, finished) = mpsc::channel();
+        let read = Arc::clone(&bytes);
+        thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            while let Ok(len @ 1..) = pipe.read(&mut chunk) {
+                read.lock().unwrap().extend_from_slice(&chunk[..len]);
+            }
+            let _ = tx.send(());
+        });
+
+        Drain { bytes, finished }
+    }
+
+    /// Everything read once the pipe closes, or whatever had come through by then if it's
+    /// still open after `wait`.
+    fn collect(self, wait: Option<Duration>) -> String {
+        let _ = match wait {
+            None => self.finished.recv().ok(),
+            Some(wait) => self.finished.recv_timeout(wait).ok(),
+        };
+        let bytes = self.bytes.lock().unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+/// Waits for `child` to exit, killing it once `timeout` passes.
+fn wait(child: &mut Child, timeout: Option<Duration>) -> Option<std::process::ExitStatus> {
+    let Some(timeout) = timeout else {
+        return Some(child.wait().expect("could not wait on external solver"));
+    };
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait().expect("could not wait on external solver") {
+            return Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        thread::sleep(Duration::from_millis(2));
+    }
+}
+
+fn parse_answers(stdout: &str) -> Result<(String, String), String> {
+    let answer = |part: u8| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("Solution {part}:")))
+            .map(|answer| answer.trim().to_string())
+            .ok_or_else(|| format!("printed no \"Solution {part}:\" line"))
+    };
+
+    Ok((answer(1)?, answer(2)?))
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use glam::{IVec2, ivec2};
+use serde::{Serialize, Serializer};
+
+use crate::direction::{Compass, Direction};
+
+/// Cells addressed by `IVec2`, whether every one of them is kept or only the ones that were set.
+pub trait Storage<T> {
+    fn get(&self, pos: IVec2) -> Option<&T>;
+
+    /// Every cell that's there with its position, in no particular order.
+    fn cells<'a>(&'a self) -> impl Iterator<Item = (IVec2, &'a T)>
+    where
+        T: 'a;
+
+    fn contains(&self, pos: IVec2) -> bool {
+        self.get(pos).is_some()
+    }
+
+    /// The smallest corner any cell is at and the position just past the largest one, or
+    /// `None` when there are no cells at all.
+    fn bounds(&self) -> Option<(IVec2, IVec2)> {
+        self.cells().fold(None, |bounds, (pos, _)| match bounds {
+            None => Some((pos, pos + IVec2::ONE)),
+            Some((min, max)) => Some((min.min(pos), max.max(pos + IVec2::ONE))),
+        })
+    }
+}
+
+/// A rectangle of cells stored row by row, addressed by `IVec2` with x to the right and y
+/// down from the top left corner.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// One row per line of `input`, turning each character into a cell with `cell`.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> T) -> Grid<T> {
+        Grid::from_rows(
+            input
+                .lines()
+                .map(|line| line.chars().map(&mut cell).collect())
+                .collect(),
+        )
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Grid<T> {
+        let width = rows.first().map_or(0, Vec::len);
+        let height = rows.len();
+        assert!(
+            rows.iter().all(|row| row.len() == width),
+            "grid rows aren't all the same length"
+        );
+
+        Grid {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The position just past the bottom right corner.
+    pub fn size(&self) -> IVec2 {
+        ivec2(self.width as i32, self.height as i32)
+    }
+
+    pub fn contains(&self, pos: IVec2) -> bool {
+        (0..self.width as i32).contains(&pos.x) && (0..self.height as i32).contains(&pos.y)
+    }
+
+    fn index_of(&self, pos: IVec2) -> Option<usize> {
+        self.contains(pos)
+            .then(|| pos.y as usize * self.width + pos.x as usize)
+    }
+
+    pub fn get(&self, pos: IVec2) -> Option<&T> {
+        self.index_of(pos).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, pos: IVec2) -> Option<&mut T> {
+        self.index_of(pos).map(|i| &mut self.cells[i])
+    }
+
+    /// Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = IVec2> + use<T> {
+        let (width, height) = (self.width as i32, self.height as i32);
+        (0..height).flat_map(move |y| (0..width).map(move |x| ivec2(x, y)))
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// The positions of the cells `matches` picks out, row by row.
+    pub fn find_all(&self, matches: impl Fn(&T) -> bool) -> Vec<IVec2> {
+        self.iter()
+            .filter(|(_, cell)| matches(cell))
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// The positions above, right of, below and left of `pos` that are on the grid.
+    pub fn neighbours4(&self, pos: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        Direction::ALL
+            .into_iter()
+            .map(move |dir| pos + dir.to_ivec2())
+            .filter(|next| self.contains(*next))
+    }
+
+    /// The positions around `pos`, diagonals included, that are on the grid.
+    pub fn neighbours8(&self, pos: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        Compass::ALL
+            .into_iter()
+            .map(move |dir| pos + dir.to_ivec2())
+            .filter(|next| self.contains(*next))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks of an empty grid would be one empty row rather than none
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells.iter().skip(x).step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Storage<T> for Grid<T> {
+    fn get(&self, pos: IVec2) -> Option<&T> {
+        Grid::get(self, pos)
+    }
+
+    fn cells<'a>(&'a self) -> impl Iterator<Item = (IVec2, &'a T)>
+    where
+        T: 'a,
+    {
+        self.iter()
+    }
+
+    fn contains(&self, pos: IVec2) -> bool {
+        Grid::contains(self, pos)
+    }
+
+    fn bounds(&self) -> Option<(IVec2, IVec2)> {
+        (!self.cells.is_empty()).then(|| (IVec2::ZERO, self.size()))
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(size: IVec2, cell: T) -> Grid<T> {
+        let (width, height) = (size.x as usize, size.y as usize);
+        Grid {
+            width,
+            height,
+            cells: vec![cell; width * height],
+        }
+    }
+
+    /// The same cells in a hash map, the way the grid days used to keep them.
+    pub fn to_sparse(&self) -> SparseGrid<T> {
+        self.iter().map(|(pos, cell)| (pos, cell.clone())).collect()
+    }
+
+    /// Swaps rows for columns, mirroring the grid along its top left to bottom right diagonal.
+    pub fn transpose(&self) -> Grid<T> {
+        Grid::from_rows(
+            self.columns()
+                .map(|column| column.cloned().collect())
+                .collect(),
+        )
+    }
+
+    /// Turns the grid a quarter turn clockwise, so the left column becomes the top row.
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        Grid::from_rows(
+            self.columns()
+                .map(|column| {
+                    let mut row: Vec<_> = column.cloned().collect();
+                    row.reverse();
+                    row
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Only the cells that were set, so it can be as big as it likes, negative coordinates
+/// included, without paying for the space in between.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<IVec2, T>,
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> SparseGrid<T> {
+        SparseGrid {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> SparseGrid<T> {
+        SparseGrid::new()
+    }
+}
+
+impl<T> FromIterator<(IVec2, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (IVec2, T)>>(cells: I) -> SparseGrid<T> {
+        SparseGrid {
+            cells: cells.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Storage<T> for SparseGrid<T> {
+    fn get(&self, pos: IVec2) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    fn cells<'a>(&'a self) -> impl Iterator<Item = (IVec2, &'a T)>
+    where
+        T: 'a,
+    {
+        self.cells.iter().map(|(pos, cell)| (*pos, cell))
+    }
+}
+
+impl<T> Index<IVec2> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: IVec2) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{pos} is off the {}x{} grid", self.width, self.height))
+    }
+}
+
+impl<T> IndexMut<IVec2> for Grid<T> {
+    fn index_mut(&mut self, pos: IVec2) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{pos} is off the {width}x{height} grid"))
+    }
+}
+
+/// Each cell's own `Display`, a row per line.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serialises as a list of rows, the same as the `Vec<Vec<T>>` it's usually parsed from.
+impl<T: Serialize> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows())
+    }
+}
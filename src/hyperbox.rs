@@ -0,0 +1,303 @@
+use std::{array, ops::Range};
+
+use crate::interval::{Integer, intersect};
+
+/// Every point whose coordinates each fall in that axis's half-open range, empty if any of
+/// the ranges is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperbox<T, const N: usize>([Range<T>; N]);
+
+impl<T: Integer, const N: usize> Hyperbox<T, N> {
+    pub fn new(axes: [Range<T>; N]) -> Hyperbox<T, N> {
+        Hyperbox(axes)
+    }
+
+    /// The same box with `axis` covering `range` instead.
+    pub fn with(&self, axis: usize, range: Range<T>) -> Hyperbox<T, N> {
+        let mut result = self.clone();
+        result.0[axis] = range;
+        result
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().any(Range::is_empty)
+    }
+
+    pub fn contains(&self, point: &[T; N]) -> bool {
+        self.0.iter().zip(point).all(|(range, n)| range.contains(n))
+    }
+
+    /// How many points are in the box.
+    pub fn volume(&self) -> u128 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.0
+            .iter()
+            .map(|range| (range.end.to_i128() - range.start.to_i128()) as u128)
+            .product()
+    }
+
+    pub fn intersection(&self, other: &Hyperbox<T, N>) -> Hyperbox<T, N> {
+        Hyperbox(array::from_fn(|axis| {
+            intersect(&self.0[axis], &other.0[axis])
+        }))
+    }
+
+    /// Cuts the box across `axis` into the points below `threshold` and the rest, either of
+    /// which may be empty.
+    pub fn split(&self, axis: usize, threshold: T) -> (Hyperbox<T, N>, Hyperbox<T, N>) {
+        let range = &self.0[axis];
+        let threshold = threshold.max(range.start).min(range.end);
+
+        (
+            self.with(axis, range.start..threshold),
+            self.with(axis, threshold..range.end),
+        )
+    }
+
+    /// The points in this box but not in `other`, as at most `2 * N` boxes that don't
+    /// overlap.
+    pub fn difference(&self, other: &Hyperbox<T, N>) -> Vec<Hyperbox<T, N>> {
+        let overlap = self.intersection(other);
+        if overlap.is_empty() {
+            return [self.clone()]
+                .into_iter()
+                .filter(|b| !b.is_empty())
+                .collect();
+        }
+
+        // peel off the slabs either side of the overlap one axis at a time, leaving the
+        // overlap itself
+        let mut pieces = vec![];
+        let mut rest = self.clone();
+        for axis in 0..N {
+            let (below, middle) = rest.split(axis, overlap.0[axis].start);
+            let (middle, above) = middle.split(axis, overlap.0[axis].end);
+            pieces.extend([below, above].into_iter().filter(|b| !b.is_empty()));
+            rest = middle;
+        }
+
+        pieces
+    }
+}
+
+/// Boxes that never overlap, so the volume of the whole set is the sum of theirs.
+#[derive(Debug, Clone)]
+pub struct BoxSet<T, const N: usize>(Vec<Hyperbox<T, N>>);
+
+impl<T: Integer, const N: usize> Default for BoxSet<T, N> {
+    fn default() -> BoxSet<T, N> {
+        BoxSet::new()
+    }
+}
+
+impl<T: Integer, const N: usize> BoxSet<T, N> {
+    pub fn new() -> BoxSet<T, N> {
+        BoxSet(vec![])
+    }
+
+    /// Adds the parts of `new` that aren't in the set already.
+    pub fn insert(&mut self, new: Hyperbox<T, N>) {
+        let mut pieces = vec![new];
+        for existing in &self.0 {
+            pieces = pieces.iter().flat_map(|p| p.difference(existing)).collect();
+        }
+        self.0.extend(pieces);
+    }
+
+    /// Takes out every point that's in `cut`.
+    pub fn subtract(&mut self, cut: &Hyperbox<T, N>) {
+        self.0 = self.0.iter().flat_map(|b| b.difference(cut)).collect();
+    }
+
+    pub fn contains(&self, point: &[T; N]) -> bool {
+        self.0.iter().any(|b| b.contains(point))
+    }
+
+    pub fn volume(&self) -> u128 {
+        self.0.iter().map(Hyperbox::volume).sum()
+    }
+
+    /// The boxes making up the set, none of them overlapping.
+    pub fn iter(&self) -> impl Iterator<Item = &Hyperbox<T, N>> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{self, Rng};
+
+    const CASES: u64 = 1000;
+
+    #[test]
+    fn box_volume_holds() {
+        rng::check_cases(CASES, check_volume);
+    }
+
+    #[test]
+    fn box_intersection_holds() {
+        rng::check_cases(CASES, check_intersection);
+    }
+
+    #[test]
+    fn box_split_holds() {
+        rng::check_cases(CASES, check_split);
+    }
+
+    #[test]
+    fn box_difference_holds() {
+        rng::check_cases(CASES, check_difference);
+    }
+
+    #[test]
+    fn box_set_holds() {
+        rng::check_cases(CASES, check_box_set);
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn zero_volume_boxes() {
+        let flat = Hyperbox::new([0..4, 2..2, 0..4]);
+        let backwards = Hyperbox::new([0..4, 3..1, 0..4]);
+        let cube = Hyperbox::new([0..4, 0..4, 0..4]);
+        for empty in [&flat, &backwards] {
+            assert!(empty.is_empty());
+            assert_eq!(empty.volume(), 0);
+            assert!(!empty.contains(&[0, 2, 0]));
+            assert!(empty.difference(&cube).is_empty());
+            assert_eq!(cube.difference(empty), vec![cube.clone()]);
+            assert!(cube.intersection(empty).is_empty());
+        }
+        assert_eq!(Hyperbox::<u8, 0>::new([]).volume(), 1);
+    }
+
+    #[test]
+    fn touching_boxes_dont_overlap() {
+        let (left, right) = (Hyperbox::new([0..2, 0..3]), Hyperbox::new([2..5, 0..3]));
+        assert!(left.intersection(&right).is_empty());
+        assert_eq!(left.difference(&right), vec![left.clone()]);
+        assert_eq!(
+            left.split(0, 2),
+            (left.clone(), Hyperbox::new([2..2, 0..3]))
+        );
+        assert_eq!(left.split(0, 9).1.volume(), 0);
+    }
+
+    #[test]
+    fn box_set_edges() {
+        let mut set = BoxSet::new();
+        assert_eq!(set.volume(), 0);
+        set.insert(Hyperbox::new([1..1, 0..9]));
+        assert_eq!(set.volume(), 0);
+        assert!(!set.contains(&[1, 0]));
+        set.insert(Hyperbox::new([0..3, 0..3]));
+        set.insert(Hyperbox::new([0..3, 0..3]));
+        assert_eq!(set.volume(), 9);
+        set.subtract(&Hyperbox::new([4..4, 0..3]));
+        assert_eq!(set.volume(), 9);
+        set.subtract(&Hyperbox::new([0..3, 0..3]));
+        assert_eq!(set.volume(), 0);
+        assert_eq!(set.iter().count(), 0);
+    }
+
+    /// The side of the cube the model's points live in, small enough for all of them to fit.
+    const SIDE: i8 = 5;
+
+    type ModelBox = Hyperbox<i8, 3>;
+
+    fn points() -> impl Iterator<Item = [i8; 3]> {
+        (0..SIDE).flat_map(|x| (0..SIDE).flat_map(move |y| (0..SIDE).map(move |z| [x, y, z])))
+    }
+
+    fn model(contains: impl Fn(&[i8; 3]) -> bool) -> u128 {
+        points()
+            .enumerate()
+            .filter(|(_, point)| contains(point))
+            .fold(0, |acc, (i, _)| acc | 1 << i)
+    }
+
+    /// Ranges of the cube's sides, with some of them empty or backwards.
+    fn random_box(rng: &mut Rng) -> ModelBox {
+        Hyperbox::new(array::from_fn(|_| {
+            rng.range(0..SIDE as i64 + 1) as i8..rng.range(0..SIDE as i64 + 1) as i8
+        }))
+    }
+
+    fn check_volume(rng: &mut Rng) -> Result<(), String> {
+        let b = random_box(rng);
+        let expected = model(|p| b.contains(p)).count_ones() as u128;
+        if b.volume() != expected {
+            return Err(format!("{b:?} has volume {}, not {expected}", b.volume()));
+        }
+
+        Ok(())
+    }
+
+    fn check_intersection(rng: &mut Rng) -> Result<(), String> {
+        let (a, b) = (random_box(rng), random_box(rng));
+        let found = a.intersection(&b);
+        if model(|p| found.contains(p)) != model(|p| a.contains(p) && b.contains(p)) {
+            return Err(format!("{a:?} intersected with {b:?} is {found:?}"));
+        }
+
+        Ok(())
+    }
+
+    fn check_split(rng: &mut Rng) -> Result<(), String> {
+        let b = random_box(rng);
+        let (axis, threshold) = (rng.below(3), rng.range(-1..SIDE as i64 + 2) as i8);
+        let (below, above) = b.split(axis, threshold);
+        let expected_below = model(|p| b.contains(p) && p[axis] < threshold);
+        let expected_above = model(|p| b.contains(p) && p[axis] >= threshold);
+        if model(|p| below.contains(p)) != expected_below
+            || model(|p| above.contains(p)) != expected_above
+        {
+            return Err(format!(
+                "{b:?} split across {axis} at {threshold} is {below:?} and {above:?}"
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn check_difference(rng: &mut Rng) -> Result<(), String> {
+        let (a, b) = (random_box(rng), random_box(rng));
+        let pieces = a.difference(&b);
+        let expected = model(|p| a.contains(p) && !b.contains(p));
+        let covered = pieces
+            .iter()
+            .fold(0, |acc, piece| acc | model(|p| piece.contains(p)));
+        let volume: u128 = pieces.iter().map(Hyperbox::volume).sum();
+        if covered != expected || volume != expected.count_ones() as u128 || pieces.len() > 6 {
+            return Err(format!("{a:?} without {b:?} is {pieces:?}"));
+        }
+
+        Ok(())
+    }
+
+    fn check_box_set(rng: &mut Rng) -> Result<(), String> {
+        let mut set = BoxSet::new();
+        let mut expected = 0;
+        let mut history = vec![];
+        for _ in 0..rng.range(1..8) {
+            let b = random_box(rng);
+            if rng.chance(0.7) {
+                expected |= model(|p| b.contains(p));
+                set.insert(b.clone());
+                history.push(format!("insert {b:?}"));
+            } else {
+                expected &= !model(|p| b.contains(p));
+                set.subtract(&b);
+                history.push(format!("subtract {b:?}"));
+            }
+        }
+        if model(|p| set.contains(p)) != expected || set.volume() != expected.count_ones() as u128 {
+            return Err(format!("after {} it holds {:?}", history.join(", "), set));
+        }
+
+        Ok(())
+    }
+}
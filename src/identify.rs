@@ -0,0 +1,44 @@
+use std::fs::read_to_string;
+
+use crate::days;
+
+/// Guesses which day `path` is an input for by running every day's parser over it, then
+/// optionally solves it as that day.
+pub fn identify(path: &str, solve: bool) {
+    let input = read_to_string(path).unwrap_or_else(|e| panic!("could not read {path}: {e}"));
+    let total = input.trim_end().len().max(1);
+
+    let mut candidates: Vec<_> = days::all()
+        .map(|(day, entry)| (day, (entry.parsed_len)(&input).min(total)))
+        .filter(|(_, len)| *len > 0)
+        .collect();
+    candidates.sort_by_key(|&(day, len)| (std::cmp::Reverse(len), day));
+
+    let Some(&(best_day, best_len)) = candidates.first() else {
+        println!("{path}: no day could parse this");
+        return;
+    };
+    let tied: Vec<_> = candidates
+        .iter()
+        .filter(|(_, len)| *len == best_len)
+        .map(|(day, _)| day.to_string())
+        .collect();
+    if tied.len() > 1 {
+        println!("{path}: ambiguous between days {}", tied.join(", "));
+    } else {
+        println!("{path}: best match is day {best_day}");
+    }
+    for (day, len) in &candidates {
+        println!(
+            "  day {day:>2}  {:>5.1}%  ({len}/{total} bytes)",
+            *len as f64 * 100.0 / total as f64
+        );
+    }
+
+    if solve {
+        let (p1, p2) = (days::get(best_day).unwrap().solve)(&input);
+        println!("Day {best_day}");
+        println!("Solution 1: {p1}");
+        println!("Solution 2: {p2}");
+    }
+}
@@ -0,0 +1,321 @@
+use std::{fmt, ops::Range};
+
+/// The integer types an `IntervalSet` can hold, going through `i128` for the arithmetic so
+/// none of them overflow on the way.
+pub trait Integer: Copy + Ord + fmt::Debug {
+    fn to_i128(self) -> i128;
+    /// `None` if `n` doesn't fit.
+    fn from_i128(n: i128) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {
+            fn to_i128(self) -> i128 {
+                self as i128
+            }
+
+            fn from_i128(n: i128) -> Option<$t> {
+                <$t>::try_from(n).ok()
+            }
+        }
+    )*};
+}
+
+impl_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// The overlap of two ranges, empty (but still starting inside `a`'s bounds) if they don't
+/// meet.
+pub fn intersect<T: Integer>(a: &Range<T>, b: &Range<T>) -> Range<T> {
+    let start = a.start.max(b.start);
+    start..a.end.min(b.end).max(start)
+}
+
+/// A set of integers kept as the fewest half-open ranges that cover it: sorted, with no
+/// empty ranges and a gap between each one and the next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T>(Vec<Range<T>>);
+
+impl<T: Integer> Default for IntervalSet<T> {
+    fn default() -> IntervalSet<T> {
+        IntervalSet::new()
+    }
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet(vec![])
+    }
+
+    /// Adds `range` at the end, merging it into the last one if they touch. The ranges must
+    /// come in sorted by start for the result to stay normalised.
+    fn push(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        match self.0.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => self.0.push(range),
+        }
+    }
+
+    /// The smallest number in the set.
+    pub fn first(&self) -> Option<T> {
+        self.0.first().map(|range| range.start)
+    }
+
+    /// The ranges making up the set, in order.
+    #[cfg(test)]
+    pub fn iter(&self) -> impl Iterator<Item = &Range<T>> {
+        self.0.iter()
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = IntervalSet::new();
+        let (mut a, mut b) = (self.0.iter().peekable(), other.0.iter().peekable());
+        // merge the two sorted lists, so `push` sees every start in order
+        while let Some(range) = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) if y.start < x.start => b.next(),
+            (Some(_), _) => a.next(),
+            (None, _) => b.next(),
+        } {
+            result.push(range.clone());
+        }
+
+        result
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.0.get(i), other.0.get(j)) {
+            result.push(intersect(a, b));
+            // whichever ends first can't meet anything further along the other
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        result
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = IntervalSet::new();
+        let mut j = 0;
+        for range in &self.0 {
+            while other.0.get(j).is_some_and(|cut| cut.end <= range.start) {
+                j += 1;
+            }
+            let mut start = range.start;
+            for cut in other.0[j..].iter().take_while(|cut| cut.start < range.end) {
+                result.push(start..cut.start);
+                start = start.max(cut.end);
+            }
+            result.push(start..range.end);
+        }
+
+        result
+    }
+
+    /// Every number moved along by `by`, which panics if one moves out of `T`'s range.
+    pub fn shift(&self, by: i128) -> IntervalSet<T> {
+        let moved = |n: T| T::from_i128(n.to_i128() + by).expect("shifted out of range");
+        IntervalSet(
+            self.0
+                .iter()
+                .map(|range| moved(range.start)..moved(range.end))
+                .collect(),
+        )
+    }
+}
+
+impl<T: Integer> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> IntervalSet<T> {
+        let mut set = IntervalSet::new();
+        set.push(range);
+        set
+    }
+}
+
+impl<T: Integer> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(ranges: I) -> IntervalSet<T> {
+        let mut ranges: Vec<_> = ranges.into_iter().collect();
+        ranges.sort_by_key(|range| range.start);
+        let mut set = IntervalSet::new();
+        for range in ranges {
+            set.push(range);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{self, Rng};
+
+    const CASES: u64 = 1000;
+
+    #[test]
+    fn normalised_holds() {
+        rng::check_cases(CASES, check_normalised);
+    }
+
+    #[test]
+    fn union_holds() {
+        rng::check_cases(CASES, check_union);
+    }
+
+    #[test]
+    fn intersection_holds() {
+        rng::check_cases(CASES, check_intersection);
+    }
+
+    #[test]
+    fn difference_holds() {
+        rng::check_cases(CASES, check_difference);
+    }
+
+    #[test]
+    fn shift_holds() {
+        rng::check_cases(CASES, check_shift);
+    }
+
+    #[test]
+    fn empty_sets() {
+        let empty = IntervalSet::<i64>::new();
+        let set = IntervalSet::from(3..7);
+        assert_eq!(empty.first(), None);
+        assert_eq!(IntervalSet::from(5..5), empty);
+        assert_eq!(IntervalSet::from_iter([4..4, 9..9]), empty);
+        assert_eq!(empty.union(&empty), empty);
+        assert_eq!(empty.union(&set), set);
+        assert_eq!(set.union(&empty), set);
+        assert_eq!(empty.intersection(&set), empty);
+        assert_eq!(set.intersection(&empty), empty);
+        assert_eq!(set.difference(&empty), set);
+        assert_eq!(empty.difference(&set), empty);
+        assert_eq!(set.difference(&set), empty);
+        assert_eq!(empty.shift(i128::MAX), empty);
+    }
+
+    #[test]
+    fn touching_ranges_merge() {
+        let set = IntervalSet::from_iter([5..8, 0..5, 10..12]);
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), [0..8, 10..12]);
+        assert_eq!(
+            IntervalSet::from(0..3).union(&IntervalSet::from(3..4)),
+            IntervalSet::from(0..4)
+        );
+    }
+
+    #[test]
+    fn type_edges() {
+        let set = IntervalSet::from(u8::MIN..u8::MAX);
+        assert_eq!(set.shift(0), set);
+        assert_eq!(set.first(), Some(0));
+        assert_eq!(
+            IntervalSet::from(250u8..255).shift(-250),
+            IntervalSet::from(0..5)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "shifted out of range")]
+    fn shift_out_of_range() {
+        IntervalSet::from(250u8..255).shift(1);
+    }
+
+    /// Where the numbers the bits stand for start, below zero so signed edge cases come up.
+    const MODEL_START: i16 = -20;
+    const MODEL_END: i16 = MODEL_START + 64;
+
+    fn bit(n: i16) -> u64 {
+        1 << (n - MODEL_START)
+    }
+
+    fn model(set: &IntervalSet<i16>) -> u64 {
+        set.iter()
+            .flat_map(Range::clone)
+            .map(bit)
+            .fold(0, |acc, b| acc | b)
+    }
+
+    /// A few ranges inside the model's numbers, some empty, overlapping or touching, in no order.
+    fn random_set(rng: &mut Rng) -> IntervalSet<i16> {
+        (0..rng.below(5))
+            .map(|_| {
+                let start = rng.range(MODEL_START as i64..MODEL_END as i64) as i16;
+                start..(start + rng.below(12) as i16).min(MODEL_END)
+            })
+            .collect()
+    }
+
+    fn check_normalised(rng: &mut Rng) -> Result<(), String> {
+        normalised(&random_set(rng))
+    }
+
+    fn check_operation(
+        rng: &mut Rng,
+        name: &str,
+        operation: fn(&IntervalSet<i16>, &IntervalSet<i16>) -> IntervalSet<i16>,
+        bits: fn(u64, u64) -> u64,
+    ) -> Result<(), String> {
+        let (a, b) = (random_set(rng), random_set(rng));
+        let found = operation(&a, &b);
+        if model(&found) != bits(model(&a), model(&b)) {
+            return Err(format!("{a:?} {name} {b:?} = {found:?}"));
+        }
+        normalised(&found)
+    }
+
+    fn normalised(set: &IntervalSet<i16>) -> Result<(), String> {
+        let ranges: Vec<_> = set.iter().collect();
+        if ranges.iter().any(|range| range.is_empty())
+            || ranges.windows(2).any(|pair| pair[0].end >= pair[1].start)
+        {
+            return Err(format!("{set:?} isn't normalised"));
+        }
+
+        Ok(())
+    }
+
+    fn check_union(rng: &mut Rng) -> Result<(), String> {
+        check_operation(rng, "union", IntervalSet::union, |a, b| a | b)
+    }
+
+    fn check_intersection(rng: &mut Rng) -> Result<(), String> {
+        check_operation(rng, "intersection", IntervalSet::intersection, |a, b| a & b)
+    }
+
+    fn check_difference(rng: &mut Rng) -> Result<(), String> {
+        check_operation(rng, "difference", IntervalSet::difference, |a, b| a & !b)
+    }
+
+    fn check_shift(rng: &mut Rng) -> Result<(), String> {
+        let set = random_set(rng);
+        // only as far as keeps every number inside the model
+        let (low, high) = (
+            set.iter().map(|range| range.start).min(),
+            set.iter().map(|range| range.end).max(),
+        );
+        let by = match (low, high) {
+            (Some(low), Some(high)) => {
+                rng.range((MODEL_START - low) as i64..(MODEL_END - high + 1) as i64)
+            }
+            _ => rng.range(-10..10),
+        };
+        let found = set.shift(by as i128);
+        let expected = if by < 0 {
+            model(&set) >> -by
+        } else {
+            model(&set) << by
+        };
+        if model(&found) != expected {
+            return Err(format!("{set:?} shifted by {by} is {found:?}"));
+        }
+        normalised(&found)
+    }
+}
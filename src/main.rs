@@ -1,48 +1,362 @@
+// a build with only some of the days leaves helpers that only the others use, which the
+// full build still checks
+#![cfg_attr(not(feature = "all-days"), allow(unused))]
+
+mod cli;
+mod compare;
+mod config;
+mod cycle;
 mod days;
-use days::{
-    day1, day2, day3, day4, day5, day6, day7, day8, day9, day10, day11, day12, day13, day14, day15,
-    day16, day17, day18, day19,
-};
+mod difftest;
+#[cfg(feature = "grid")]
+mod direction;
+mod explain;
+mod external;
+#[cfg(feature = "grid")]
+mod grid;
+mod hyperbox;
+mod identify;
+mod interval;
+mod math;
+mod minimize;
+mod parse;
+#[cfg(feature = "grid")]
+mod polygon;
+mod rng;
+mod roundtrip;
+mod scale;
+mod search;
+mod watch;
+use cli::Args;
+use config::{Config, Format};
+use days::Solver;
 
-use std::env;
+use std::{
+    env,
+    fs::{File, read_to_string},
+    io::{self, BufReader},
+    panic,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        panic!("improper amount of args");
-    }
-
-    let day = args[1].parse::<u8>().expect("not a valid day");
-
-    let func = get_day_solver(day);
-
-    let (p1, p2) = func();
-
-    println!("Solution 1: {p1}");
-    println!("Solution 2: {p2}");
-}
-
-fn get_day_solver(day: u8) -> fn() -> (String, String) {
-    match day {
-        1 => day1::solve,
-        2 => day2::solve,
-        3 => day3::solve,
-        4 => day4::solve,
-        5 => day5::solve,
-        6 => day6::solve,
-        7 => day7::solve,
-        8 => day8::solve,
-        9 => day9::solve,
-        10 => day10::solve,
-        11 => day11::solve,
-        12 => day12::solve,
-        13 => day13::solve,
-        14 => day14::solve,
-        15 => day15::solve,
-        16 => day16::solve,
-        17 => day17::solve,
-        18 => day18::solve,
-        19 => day19::solve,
-        _ => unimplemented!(),
+    let args = Args::parse(env::args().skip(1));
+    let config = Config::load(&args);
+
+    if let Some(threads) = config.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("could not build thread pool");
+        days::set_parallel(true);
+    }
+
+    let positional: Vec<_> = args.positional.iter().map(String::as_str).collect();
+    match positional.as_slice() {
+        ["config", "show"] => print!("{}", config.show()),
+        ["all"] => {
+            for (day, _) in days::all() {
+                if !config.day(day).skip {
+                    run_day(day, &config, true);
+                }
+            }
+        }
+        ["bench", day] => bench_day(parse_day(day), &config),
+        ["watch", day] => {
+            let day = parse_day(day);
+            watch::watch_day(day, get_day_solver(day, &config), &config);
+        }
+        ["identify", path] => identify::identify(path, args.switch("solve")),
+        ["difftest"] => difftest::difftest(
+            None,
+            args.parsed_flag("cases").unwrap_or(200),
+            args.parsed_flag("seed").unwrap_or(0),
+        ),
+        ["difftest", day] => difftest::difftest(
+            Some(parse_day(day)),
+            args.parsed_flag("cases").unwrap_or(200),
+            args.parsed_flag("seed").unwrap_or(0),
+        ),
+        ["roundtrip"] => roundtrip::roundtrip(
+            None,
+            args.parsed_flag("cases").unwrap_or(200),
+            args.parsed_flag("seed").unwrap_or(0),
+            &config,
+        ),
+        ["roundtrip", day] => roundtrip::roundtrip(
+            Some(parse_day(day)),
+            args.parsed_flag("cases").unwrap_or(200),
+            args.parsed_flag("seed").unwrap_or(0),
+            &config,
+        ),
+        ["verify"] => compare::verify(None, &config),
+        ["verify", day] => compare::verify(Some(parse_day(day)), &config),
+        ["parse", day] => {
+            let day = parse_day(day);
+            let model = days::get(day).expect("day not implemented").model;
+            let model = model(&read_input(day, &config));
+            println!("{}", serde_json::to_string_pretty(&model).unwrap());
+        }
+        ["gen", day] => {
+            let generate = days::get(parse_day(day))
+                .expect("day not implemented")
+                .generate;
+            let seed = args.parsed_flag("seed").unwrap_or(0);
+            let size = args.parsed_flag("size").unwrap_or(1);
+            println!("{}", generate(&mut rng::Rng::new(seed), size));
+        }
+        ["scale", day] => {
+            let day = parse_day(day);
+            let budget = args.parsed_flag("budget").unwrap_or(1.0);
+            scale::scale(
+                day,
+                &days::get(day).expect("day not implemented"),
+                get_day_solver(day, &config),
+                &config,
+                Duration::from_secs_f64(budget),
+                args.parsed_flag("max-size").unwrap_or(1 << 12),
+                args.parsed_flag("seed").unwrap_or(0),
+            );
+        }
+        ["minimize", day, path] => minimize::minimize(parse_day(day), path, &config, &args),
+        [day] => run_day(parse_day(day), &config, false),
+        _ => panic!("improper amount of args"),
+    }
+}
+
+fn parse_day(day: &str) -> u8 {
+    day.parse::<u8>().expect("not a valid day")
+}
+
+fn read_input(day: u8, config: &Config) -> String {
+    load_input(day, config).unwrap_or_else(|e| input_error(day, config, e))
+}
+
+/// Gives up on an input that can't be read, saying where to get it when it just isn't in
+/// the input directory yet.
+fn input_error(day: u8, config: &Config, e: io::Error) -> ! {
+    let path = config.input_path(day);
+    if e.kind() == io::ErrorKind::NotFound && config.input.is_none() {
+        panic!(
+            "could not read {}: {e}; download it from {}",
+            path.display(),
+            config.input_url(day)
+        );
+    }
+
+    panic!("could not read {}: {e}", path.display())
+}
+
+/// The day's input from `--input` or the input directory. With `embed-inputs`, the copy
+/// baked into the binary stands in when there's no `--input` and the file isn't there.
+pub(crate) fn load_input(day: u8, config: &Config) -> io::Result<String> {
+    let path = config.input_path(day);
+    if path.as_os_str() == "-" {
+        return io::read_to_string(io::stdin());
+    }
+
+    let input = read_to_string(&path);
+    #[cfg(feature = "embed-inputs")]
+    if input.is_err()
+        && config.input.is_none()
+        && let Some(embedded) = days::embedded_input(day)
+    {
+        return Ok(embedded.to_string());
+    }
+    input
+}
+
+fn run_day(day: u8, config: &Config, with_header: bool) {
+    if config.implementation.as_deref() == Some("all") {
+        let input = read_input(day, config);
+        compare::compare_implementations(day, &input, config, with_header);
+        return;
+    }
+    let external = config
+        .implementation
+        .as_ref()
+        .filter(|name| days::get(day).is_some_and(|entry| entry.implementation(name).is_none()))
+        .and_then(|name| config.external_solvers(day).remove(name));
+
+    let solve: Box<dyn FnOnce() -> (String, String) + Send> = if config.stream {
+        if config.implementation.is_some() {
+            panic!("--stream only has the one implementation, so it can't take --impl");
+        }
+        let stream = days::get(day)
+            .expect("day not implemented")
+            .stream
+            .unwrap_or_else(|| panic!("day {day} can't be solved a line at a time"));
+        let path = config.input_path(day);
+        if path.as_os_str() == "-" {
+            Box::new(move || stream(&mut io::stdin().lock()))
+        } else {
+            match File::open(&path) {
+                Ok(file) => Box::new(move || stream(&mut BufReader::new(file))),
+                #[cfg(feature = "embed-inputs")]
+                Err(_) if config.input.is_none() && days::embedded_input(day).is_some() => {
+                    let embedded = days::embedded_input(day).unwrap();
+                    Box::new(move || stream(&mut embedded.as_bytes()))
+                }
+                Err(e) => input_error(day, config, e),
+            }
+        }
+    } else if let Some(command) = external {
+        let input = read_input(day, config);
+        let timeout = config.day(day).timeout();
+        Box::new(move || {
+            let run = external::run_external(&command, &input, timeout);
+            eprint!("{}", run.stderr);
+            run.solution
+                .unwrap_or_else(|reason| panic!("external solver {reason}"))
+        })
+    } else {
+        let func = get_day_solver(day, config);
+        let input = read_input(day, config);
+        Box::new(move || func(&input))
+    };
+
+    if config.explain {
+        explain::start();
+    }
+    let solution = solve_with_timeout(solve, config.day(day).timeout());
+    if config.explain {
+        explain::render(day, &explain::finish(), config.format);
     }
+    let Some((p1, p2)) = solution else {
+        eprintln!("Day {day} timed out");
+        return;
+    };
+
+    match config.format {
+        Format::Text => {
+            if with_header {
+                println!("Day {day}");
+            }
+            println!("Solution 1: {p1}");
+            println!("Solution 2: {p2}");
+        }
+        Format::Json => {
+            println!(
+                "{}",
+                serde_json::json!({ "day": day, "part1": p1, "part2": p2 })
+            );
+        }
+    }
+}
+
+fn bench_day(day: u8, config: &Config) {
+    let solvers = if config.implementation.as_deref() == Some("all") {
+        days::get(day)
+            .expect("day not implemented")
+            .compared_implementations()
+    } else {
+        let name = config.implementation.as_deref().unwrap_or("default");
+        vec![(name, get_day_solver(day, config))]
+    };
+    let input = read_input(day, config);
+
+    // with the pool switched on, time each solver without it too, to see what it bought
+    let modes: &[bool] = if cfg!(feature = "parallel") && config.threads.is_some() {
+        &[false, true]
+    } else {
+        &[false]
+    };
+
+    for (name, func) in solvers {
+        let mut sequential_mean = None;
+        for &parallel in modes {
+            days::set_parallel(parallel);
+            let timings: Vec<Duration> = (0..config.bench_iterations.max(1))
+                .map(|_| {
+                    let start = Instant::now();
+                    func(&input);
+                    start.elapsed()
+                })
+                .collect();
+            let min = timings.iter().min().unwrap();
+            let max = timings.iter().max().unwrap();
+            let mean = timings.iter().sum::<Duration>() / timings.len() as u32;
+            let speedup = sequential_mean
+                .filter(|_| parallel)
+                .map(|sequential: Duration| sequential.as_secs_f64() / mean.as_secs_f64());
+            sequential_mean = Some(mean);
+
+            let label = match (modes.len(), parallel) {
+                (1, _) => name.to_string(),
+                (_, false) => format!("{name}, sequential"),
+                (_, true) => format!("{name}, {} threads", rayon::current_num_threads()),
+            };
+            match config.format {
+                Format::Text => println!(
+                    "Day {day} ({label}): {} iterations, min {min:?}, mean {mean:?}, max {max:?}{}",
+                    timings.len(),
+                    speedup.map_or(String::new(), |speedup| format!(", speedup {speedup:.2}x"))
+                ),
+                Format::Json => println!(
+                    "{}",
+                    serde_json::json!({
+                        "day": day,
+                        "implementation": name,
+                        "parallel": parallel,
+                        "iterations": timings.len(),
+                        "min_ns": min.as_nanos() as u64,
+                        "mean_ns": mean.as_nanos() as u64,
+                        "max_ns": max.as_nanos() as u64,
+                        "speedup": speedup,
+                    })
+                ),
+            }
+        }
+    }
+}
+
+/// Runs `solve` on its own thread so a runaway day can be abandoned once `timeout` passes.
+pub(crate) fn solve_with_timeout(
+    solve: impl FnOnce() -> (String, String) + Send + 'static,
+    timeout: Option<Duration>,
+) -> Option<(String, String)> {
+    let Some(timeout) = timeout else {
+        return Some(solve());
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        // the receiver is gone if we already timed out, so there is nobody to tell
+        let _ = tx.send(solve());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(solution) => Some(solution),
+        Err(RecvTimeoutError::Timeout) => None,
+        // the solver panicked, so pass that on instead of calling it a timeout
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => unreachable!("solver thread exited without sending a solution"),
+        },
+    }
+}
+
+/// The day's solver, or the implementation `--impl` picked.
+fn get_day_solver(day: u8, config: &Config) -> Solver {
+    let entry = days::get(day).expect("day not implemented");
+    let Some(name) = &config.implementation else {
+        return entry.solve;
+    };
+
+    entry.implementation(name).unwrap_or_else(|| {
+        if config.external_solvers(day).contains_key(name) {
+            panic!("{name} is an external solver, which can only be run, compared or verified");
+        }
+        let names: Vec<_> = entry
+            .implementations()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        panic!(
+            "day {day} has no implementation {name}, try one of: {}, all",
+            names.join(", ")
+        )
+    })
 }
@@ -0,0 +1,277 @@
+/// Greatest common divisor, with `gcd(0, 0) == 0`.
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Least common multiple, or `None` if it doesn't fit in a `u64`.
+pub fn lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// The least common multiple of all of `nums`, 1 for none at all.
+pub fn lcm_all(nums: &[u64]) -> Option<u64> {
+    nums.iter().try_fold(1, |acc, &n| lcm(acc, n))
+}
+
+/// `(g, x, y)` with `a * x + b * y == g`, where `g` is the non-negative gcd of `a` and `b`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The `x` in `0..modulus` with `a * x ≡ 1`, if `a` and `modulus` are coprime.
+pub fn mod_inverse(a: u64, modulus: u64) -> Option<u64> {
+    assert!(modulus > 0, "modulus can't be zero");
+    let (g, x, _) = extended_gcd(a as i128, modulus as i128);
+
+    (g == 1).then(|| x.rem_euclid(modulus as i128) as u64)
+}
+
+/// Solves `x ≡ remainder (mod modulus)` for every pair at once, giving the smallest
+/// non-negative `x` along with the lcm of the moduli that every other solution is `x` plus a
+/// multiple of. The moduli don't have to be coprime. `None` if the congruences contradict
+/// each other or the lcm doesn't fit in a `u64`.
+pub fn crt(congruences: &[(u64, u64)]) -> Option<(u64, u64)> {
+    congruences
+        .iter()
+        .try_fold((0, 1), |(x, modulus), &(remainder, other_modulus)| {
+            assert!(other_modulus > 0, "modulus can't be zero");
+            let g = gcd(modulus, other_modulus);
+            let remainder = remainder % other_modulus;
+            let difference = remainder as i128 - x as i128;
+            if difference % g as i128 != 0 {
+                return None;
+            }
+
+            // x + modulus * k ≡ remainder, so k ≡ difference / g * (modulus / g)^-1 mod
+            // other_modulus / g
+            let step = other_modulus / g;
+            let k = (difference / g as i128).rem_euclid(step as i128) as u128
+                * mod_inverse((modulus / g) % step, step)? as u128
+                % step as u128;
+            let combined = lcm(modulus, other_modulus)?;
+
+            Some((
+                ((x as u128 + modulus as u128 * k) % combined as u128) as u64,
+                combined,
+            ))
+        })
+}
+
+/// The largest `r` with `r * r <= n`.
+pub fn isqrt(n: u64) -> u64 {
+    // the float guess is within a step or two of the answer, even where f64 runs out of bits
+    let mut r = (n as f64).sqrt() as u64;
+    while (r as u128) * (r as u128) > n as u128 {
+        r -= 1;
+    }
+    while ((r + 1) as u128) * ((r + 1) as u128) <= n as u128 {
+        r += 1;
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{self, Rng};
+
+    const CASES: u64 = 1000;
+
+    #[test]
+    fn gcd_holds() {
+        rng::check_cases(CASES, check_gcd);
+    }
+
+    #[test]
+    fn lcm_holds() {
+        rng::check_cases(CASES, check_lcm);
+    }
+
+    #[test]
+    fn extended_gcd_holds() {
+        rng::check_cases(CASES, check_extended_gcd);
+    }
+
+    #[test]
+    fn mod_inverse_holds() {
+        rng::check_cases(CASES, check_mod_inverse);
+    }
+
+    #[test]
+    fn crt_holds() {
+        rng::check_cases(CASES, check_crt);
+    }
+
+    #[test]
+    fn isqrt_holds() {
+        rng::check_cases(CASES, check_isqrt);
+    }
+
+    #[test]
+    fn crt_non_coprime_moduli() {
+        // 10 and 7 differ in parity, which both moduli fix; 2 and 4 agree mod gcd(4, 6) = 2
+        assert_eq!(crt(&[(10, 4), (7, 6)]), None);
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(3, 6), (3, 6)]), Some((3, 6)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+    }
+
+    #[test]
+    fn crt_edges() {
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(5, 1)]), Some((0, 1)));
+        assert_eq!(crt(&[(17, 5)]), Some((2, 5)));
+        assert_eq!(crt(&[(0, u64::MAX), (0, u64::MAX - 1)]), None);
+    }
+
+    #[test]
+    fn lcm_edges() {
+        assert_eq!(lcm(0, 0), Some(0));
+        assert_eq!(lcm(0, 7), Some(0));
+        assert_eq!(lcm(u64::MAX, u64::MAX), Some(u64::MAX));
+        assert_eq!(lcm(u64::MAX, 2), None);
+        assert_eq!(lcm_all(&[]), Some(1));
+        assert_eq!(lcm_all(&[4, 6, 10]), Some(60));
+    }
+
+    #[test]
+    fn isqrt_edges() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(3), 1);
+        assert_eq!(isqrt(4), 2);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+    }
+
+    #[test]
+    fn mod_inverse_edges() {
+        assert_eq!(mod_inverse(0, 1), Some(0));
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(4, 6), None);
+    }
+
+    /// Anything from 0 to `u64::MAX`, but mostly small, where the edge cases are.
+    fn number(rng: &mut Rng) -> u64 {
+        rng.next_u64() >> rng.below(64)
+    }
+
+    fn check_gcd(rng: &mut Rng) -> Result<(), String> {
+        let (a, b) = (number(rng), number(rng));
+        let g = gcd(a, b);
+        if !a.is_multiple_of(g) || !b.is_multiple_of(g) {
+            return Err(format!("gcd({a}, {b}) = {g} doesn't divide both"));
+        }
+        if g != 0 && gcd(a / g, b / g) != 1 {
+            return Err(format!("gcd({a}, {b}) = {g} isn't the greatest"));
+        }
+
+        Ok(())
+    }
+
+    fn check_lcm(rng: &mut Rng) -> Result<(), String> {
+        let (a, b) = (number(rng), number(rng));
+        let exact = if a == 0 || b == 0 {
+            0
+        } else {
+            a as u128 / gcd(a, b) as u128 * b as u128
+        };
+        let expected = u64::try_from(exact).ok();
+        if lcm(a, b) != expected {
+            return Err(format!("lcm({a}, {b}) = {:?}, not {expected:?}", lcm(a, b)));
+        }
+        if lcm_all(&[a, b]) != expected {
+            return Err(format!("lcm_all([{a}, {b}]) isn't lcm({a}, {b})"));
+        }
+
+        Ok(())
+    }
+
+    fn check_extended_gcd(rng: &mut Rng) -> Result<(), String> {
+        let (a, b) = (
+            rng.range(-1_000_000..1_000_000),
+            rng.range(-1_000_000..1_000_000),
+        );
+        let (g, x, y) = extended_gcd(a as i128, b as i128);
+        if g != gcd(a.unsigned_abs(), b.unsigned_abs()) as i128
+            || a as i128 * x + b as i128 * y != g
+        {
+            return Err(format!("extended_gcd({a}, {b}) = ({g}, {x}, {y})"));
+        }
+
+        Ok(())
+    }
+
+    fn check_mod_inverse(rng: &mut Rng) -> Result<(), String> {
+        let (a, modulus) = (number(rng), number(rng).max(1));
+        match mod_inverse(a, modulus) {
+            Some(inverse) if inverse >= modulus => Err(format!(
+                "inverse of {a} mod {modulus} is {inverse}, out of range"
+            )),
+            Some(inverse)
+                if (a as u128 * inverse as u128 % modulus as u128) as u64 != 1 % modulus =>
+            {
+                Err(format!("{inverse} isn't the inverse of {a} mod {modulus}"))
+            }
+            None if gcd(a, modulus) == 1 => Err(format!("missed the inverse of {a} mod {modulus}")),
+            Some(_) if gcd(a, modulus) != 1 => Err(format!(
+                "{a} has no inverse mod {modulus}, they share a factor"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_crt(rng: &mut Rng) -> Result<(), String> {
+        // small enough moduli that a brute force search can confirm there's no answer
+        let congruences: Vec<(u64, u64)> = (0..rng.range(1..4))
+            .map(|_| (rng.below(100) as u64, rng.range(1..40) as u64))
+            .collect();
+        let moduli: Vec<_> = congruences.iter().map(|(_, modulus)| *modulus).collect();
+        let expected_modulus = lcm_all(&moduli).unwrap();
+        let smallest = (0..expected_modulus).find(|x| {
+            congruences
+                .iter()
+                .all(|(remainder, modulus)| x % modulus == remainder % modulus)
+        });
+
+        match (crt(&congruences), smallest) {
+            (Some((x, modulus)), Some(smallest))
+                if x == smallest && modulus == expected_modulus =>
+            {
+                Ok(())
+            }
+            (None, None) => Ok(()),
+            (found, smallest) => Err(format!(
+                "crt({congruences:?}) = {found:?}, but the smallest solution is {smallest:?} mod {expected_modulus}"
+            )),
+        }
+    }
+
+    fn check_isqrt(rng: &mut Rng) -> Result<(), String> {
+        let n = number(rng);
+        let r = isqrt(n) as u128;
+        if r * r > n as u128 || (r + 1) * (r + 1) <= n as u128 {
+            return Err(format!("isqrt({n}) = {r}"));
+        }
+
+        Ok(())
+    }
+}
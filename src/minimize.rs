@@ -0,0 +1,310 @@
+use std::{
+    env,
+    fs::{File, read_to_string, remove_file, write},
+    path::PathBuf,
+    process::{self, Command},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{cli::Args, config::Config};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    /// Blank-line separated blocks, as in days 5, 13 and 19.
+    Blocks,
+    Lines,
+    /// Individual grid cells, which get replaced by a filler character rather than
+    /// removed, followed by whole columns.
+    Cells,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blocks" => Ok(Granularity::Blocks),
+            "lines" => Ok(Granularity::Lines),
+            "cells" => Ok(Granularity::Cells),
+            _ => Err(format!("unknown granularity {s}")),
+        }
+    }
+}
+
+enum Outcome {
+    Solved((String, String)),
+    Panicked(String),
+    TimedOut,
+}
+
+/// What counts as the day failing on a candidate.
+enum Failure<'a> {
+    /// A panic, mentioning the message if there is one.
+    Panic(Option<String>),
+    Hang,
+    /// Answers other than these.
+    Answers((String, String)),
+    /// Answers other than the ones this implementation gives.
+    Disagrees(&'a str),
+}
+
+/// Shrinks the input at `path` to a small one that still makes `day` fail, and prints it.
+pub fn minimize(day: u8, path: &str, config: &Config, args: &Args) {
+    let input = read_to_string(path).unwrap_or_else(|e| panic!("could not read {path}: {e}"));
+    let timeout = config.day(day).timeout().unwrap_or(DEFAULT_TIMEOUT);
+    let implementation = config.implementation.as_deref();
+    let failures: Vec<Failure> = [
+        args.switch("hangs").then_some(Failure::Hang),
+        args.flag("expect").map(|expect| {
+            let (p1, p2) = expect
+                .split_once(',')
+                .expect("--expect takes both answers, as PART1,PART2");
+            Failure::Answers((p1.to_string(), p2.to_string()))
+        }),
+        args.flag("against").map(Failure::Disagrees),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let failure = match failures.len() {
+        0 if args.switch("any-panic") => Failure::Panic(None),
+        // the panic the original input runs into, wherever it happens to be raised from
+        0 => Failure::Panic(Some(match args.flag("message") {
+            Some(message) => message.to_string(),
+            None => match run_candidate(day, &input, implementation, timeout) {
+                Outcome::Panicked(stderr) => panic_location(&stderr)
+                    .unwrap_or_else(|| panic!("no panic message in the output for {path}"))
+                    .to_string(),
+                _ => panic!("day {day} doesn't panic on {path} to begin with"),
+            },
+        })),
+        1 => failures.into_iter().next().unwrap(),
+        _ => panic!("--hangs, --expect and --against each look for a different failure, pick one"),
+    };
+    let filler = args.parsed_flag("filler").unwrap_or('.');
+    let granularities: Vec<Granularity> = match args.parsed_flag("by") {
+        Some(granularity) => vec![granularity],
+        None => vec![Granularity::Blocks, Granularity::Lines, Granularity::Cells],
+    };
+
+    let mut runs = 0usize;
+    let mut fails = |candidate: &str| {
+        runs += 1;
+        let outcome = run_candidate(day, candidate, implementation, timeout);
+        match (&failure, outcome) {
+            (Failure::Panic(message), Outcome::Panicked(stderr)) => message
+                .as_deref()
+                .is_none_or(|message| stderr.contains(message)),
+            (Failure::Hang, Outcome::TimedOut) => true,
+            (Failure::Answers(expected), Outcome::Solved(answers)) => answers != *expected,
+            // only a disagreement if the other implementation gets an answer at all
+            (Failure::Disagrees(other), Outcome::Solved(answers)) => {
+                runs += 1;
+                matches!(
+                    run_candidate(day, candidate, Some(other), timeout),
+                    Outcome::Solved(theirs) if theirs != answers
+                )
+            }
+            _ => false,
+        }
+    };
+    if !fails(&input) {
+        panic!("day {day} doesn't fail on {path} to begin with");
+    }
+
+    let minimized = shrink(&input, &granularities, filler, &mut fails);
+
+    eprintln!(
+        "minimized {} bytes to {} bytes in {runs} runs",
+        input.len(),
+        minimized.len()
+    );
+    println!("{minimized}");
+}
+
+/// The `panicked at FILE:LINE:COLUMN:` part of a panic report, leaving out the thread name
+/// since a panic on a worker thread can land on any of them.
+fn panic_location(stderr: &str) -> Option<&str> {
+    let start = stderr.find("panicked at ")?;
+    let line = &stderr[start..];
+
+    Some(line.lines().next().unwrap_or(line))
+}
+
+/// Repeatedly applies each granularity of delta debugging until none of them can remove
+/// anything else while `fails` still holds.
+pub fn shrink(
+    input: &str,
+    granularities: &[Granularity],
+    filler: char,
+    fails: &mut impl FnMut(&str) -> bool,
+) -> String {
+    let mut current = input.to_string();
+    loop {
+        let before = current.clone();
+        for granularity in granularities {
+            current = match granularity {
+                Granularity::Blocks => shrink_split(&current, "\n\n", fails),
+                Granularity::Lines => shrink_split(&current, "\n", fails),
+                Granularity::Cells if is_grid(&current) => shrink_cells(&current, filler, fails),
+                Granularity::Cells => current,
+            };
+        }
+        if current == before {
+            return current;
+        }
+    }
+}
+
+fn shrink_split(input: &str, separator: &str, fails: &mut impl FnMut(&str) -> bool) -> String {
+    let units: Vec<_> = input.split(separator).collect();
+
+    ddmin(units, |kept| fails(&kept.join(separator))).join(separator)
+}
+
+fn shrink_cells(input: &str, filler: char, fails: &mut impl FnMut(&str) -> bool) -> String {
+    let grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+    let render_cells = |kept: &[(usize, usize)]| {
+        let mut blank = vec![vec![filler; grid[0].len()]; grid.len()];
+        for &(x, y) in kept {
+            blank[y][x] = grid[y][x];
+        }
+        blank
+    };
+
+    let cells: Vec<_> = grid
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, ch)| **ch != filler)
+                .map(move |(x, _)| (x, y))
+        })
+        .collect();
+    let kept = ddmin(cells, |kept| fails(&to_text(&render_cells(kept))));
+    let grid = render_cells(&kept);
+
+    let render_columns = |kept: &[usize]| -> Vec<Vec<char>> {
+        grid.iter()
+            .map(|row| kept.iter().map(|&x| row[x]).collect())
+            .collect()
+    };
+    let kept = ddmin((0..grid[0].len()).collect(), |kept| {
+        fails(&to_text(&render_columns(kept)))
+    });
+
+    to_text(&render_columns(&kept))
+}
+
+/// Zeller's ddmin, reduced to only testing complements: finds a subset of `units`, that
+/// can't have any single chunk removed, for which `fails` still holds.
+fn ddmin<T: Clone>(mut units: Vec<T>, mut fails: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    let mut chunks = 2usize;
+    while units.len() >= 2 {
+        let chunk_len = units.len().div_ceil(chunks);
+        let reduced = (0..units.len()).step_by(chunk_len).find_map(|start| {
+            let end = (start + chunk_len).min(units.len());
+            let complement: Vec<_> = units[..start]
+                .iter()
+                .chain(&units[end..])
+                .cloned()
+                .collect();
+            fails(&complement).then_some(complement)
+        });
+
+        match reduced {
+            Some(complement) => {
+                units = complement;
+                chunks = (chunks - 1).max(2);
+            }
+            None if chunks >= units.len() => break,
+            None => chunks = (chunks * 2).min(units.len()),
+        }
+    }
+
+    units
+}
+
+fn is_grid(input: &str) -> bool {
+    let width = input.lines().next().map_or(0, str::len);
+    width > 0 && input.lines().all(|line| line.len() == width)
+}
+
+fn to_text(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Solves `candidate` in a child process, with `implementation` if one is given, so panics
+/// are contained and hangs can be killed.
+fn run_candidate(
+    day: u8,
+    candidate: &str,
+    implementation: Option<&str>,
+    timeout: Duration,
+) -> Outcome {
+    let dir = env::temp_dir();
+    let input_path: PathBuf = dir.join(format!("aoc-minimize-{}.txt", process::id()));
+    let stdout_path: PathBuf = dir.join(format!("aoc-minimize-{}.out", process::id()));
+    let stderr_path: PathBuf = dir.join(format!("aoc-minimize-{}.err", process::id()));
+    write(&input_path, candidate).expect("could not write candidate input");
+    // files rather than pipes, so a chatty solver can't fill one up and block until it's killed
+    let stdout = File::create(&stdout_path).expect("could not create stdout capture");
+    let stderr = File::create(&stderr_path).expect("could not create stderr capture");
+
+    let mut command = Command::new(env::current_exe().expect("could not find own executable"));
+    command
+        .arg(day.to_string())
+        .arg("--input")
+        .arg(&input_path)
+        // outlast our own deadline, so a hang is killed here rather than reported as solved
+        .arg("--timeout")
+        .arg((timeout.as_secs() * 2 + 1).to_string())
+        .args(["--format", "json"])
+        .env("RUST_BACKTRACE", "0")
+        .stdout(stdout)
+        .stderr(stderr);
+    if let Some(implementation) = implementation {
+        command.arg("--impl").arg(implementation);
+    }
+    let mut child = command.spawn().expect("could not spawn solver");
+
+    let deadline = Instant::now() + timeout;
+    let outcome = loop {
+        if let Some(status) = child.try_wait().expect("could not wait on solver") {
+            let stdout = read_to_string(&stdout_path).unwrap_or_default();
+            break match answers(&stdout) {
+                Some(answers) if status.success() => Outcome::Solved(answers),
+                // a run that timed out inside the child exits cleanly without answers
+                None if status.success() => Outcome::TimedOut,
+                _ => Outcome::Panicked(read_to_string(&stderr_path).unwrap_or_default()),
+            };
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break Outcome::TimedOut;
+        }
+        thread::sleep(Duration::from_millis(2));
+    };
+
+    let _ = remove_file(&input_path);
+    let _ = remove_file(&stdout_path);
+    let _ = remove_file(&stderr_path);
+    outcome
+}
+
+/// The answers the child printed as JSON.
+fn answers(stdout: &str) -> Option<(String, String)> {
+    let solution: serde_json::Value = serde_json::from_str(stdout.trim()).ok()?;
+    let part = |key| solution[key].as_str().map(str::to_string);
+
+    Some((part("part1")?, part("part2")?))
+}
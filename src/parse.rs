@@ -0,0 +1,132 @@
+use std::fmt;
+
+use nom::{
+    Finish, Parser,
+    bytes::complete::tag,
+    character::complete::{anychar, line_ending, space0, space1},
+    combinator::{map_opt, opt},
+    error::{Error, ErrorKind},
+    multi::{many1, separated_list1},
+    sequence::{delimited, terminated},
+};
+
+#[cfg(feature = "grid")]
+use crate::grid::Grid;
+#[cfg(feature = "grid")]
+use nom::combinator::{map, verify};
+
+/// The parsers the days are built from: over `&str`, with nom's own errors.
+pub trait StrParser<'a, O>: Parser<&'a str, Output = O, Error = Error<&'a str>> {}
+
+impl<'a, O, P: Parser<&'a str, Output = O, Error = Error<&'a str>>> StrParser<'a, O> for P {}
+
+/// Runs `parser` over the whole of `input`, which may end in a newline, saying where it got
+/// stuck if it didn't make it to the end.
+pub fn all<'a, O>(input: &'a str, parser: impl StrParser<'a, O>) -> Result<O, ParseError> {
+    let (rest, output) = terminated(parser, opt(line_ending))
+        .parse(input)
+        .finish()
+        .map_err(|error| ParseError::new(input, error.input, Some(error.code)))?;
+    if !rest.is_empty() {
+        return Err(ParseError::new(input, rest, None));
+    }
+
+    Ok(output)
+}
+
+/// `item` on each line.
+pub fn lines<'a, O>(item: impl StrParser<'a, O>) -> impl StrParser<'a, Vec<O>> {
+    separated_list1(line_ending, item)
+}
+
+/// `item` on each run of lines, with a blank line between runs.
+pub fn blocks<'a, O>(item: impl StrParser<'a, O>) -> impl StrParser<'a, Vec<O>> {
+    separated_list1((line_ending, line_ending), item)
+}
+
+/// Numbers on one line with spaces between them, however many.
+pub fn numbers<'a, N>(number: impl StrParser<'a, N>) -> impl StrParser<'a, Vec<N>> {
+    separated_list1(space1, number)
+}
+
+/// A run of characters on one line, each turned into a cell by `cell`, up to the first one it
+/// returns `None` for.
+pub fn cells<'a, T>(cell: impl Fn(char) -> Option<T>) -> impl StrParser<'a, Vec<T>> {
+    many1(map_opt(anychar, cell))
+}
+
+/// Lines of `cells`, all the same length.
+#[cfg(feature = "grid")]
+pub fn grid<'a, T>(cell: impl Fn(char) -> Option<T>) -> impl StrParser<'a, Grid<T>> {
+    let rows = verify(lines(cells(cell)), |rows: &Vec<Vec<T>>| {
+        rows.iter().all(|row| row.len() == rows[0].len())
+    });
+
+    map(rows, Grid::from_rows)
+}
+
+/// `Label:` and the spaces after it.
+pub fn label<'a>(label: &'a str) -> impl StrParser<'a, ()> {
+    (tag(label), tag(":"), space0).map(|_| ())
+}
+
+/// `Label N:` and the spaces after it, giving back `N`.
+pub fn header<'a, N>(label: &'a str, number: impl StrParser<'a, N>) -> impl StrParser<'a, N> {
+    delimited((tag(label), space1), number, (tag(":"), space0))
+}
+
+/// Where in the input a parser gave up, and what it was looking at there.
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    /// The parser that failed, or `None` if they all succeeded but left something over.
+    kind: Option<ErrorKind>,
+    found: String,
+}
+
+impl ParseError {
+    fn new(input: &str, at: &str, kind: Option<ErrorKind>) -> ParseError {
+        let mut offset = input.len() - at.len();
+        // a list of lines stops before the line ending ahead of the line it couldn't parse,
+        // so point at that line instead
+        if kind.is_none()
+            && let Some(next_line) = at.strip_prefix('\n').or(at.strip_prefix("\r\n"))
+            && !next_line.is_empty()
+        {
+            offset = input.len() - next_line.len();
+        }
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            kind,
+            found: input[offset..].lines().next().unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            Some(kind) => write!(f, "{} failed", kind.description())?,
+            None => write!(f, "expected the end of the input")?,
+        }
+        if !self.found.is_empty() {
+            write!(f, " at {:?}", self.found)
+        } else if self.column == 1 {
+            write!(f, " at an empty line")
+        } else {
+            write!(f, " at the end of the line")
+        }
+    }
+}
+
+/// The same as `Display`, so the location reads well from an `expect`.
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
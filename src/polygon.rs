@@ -0,0 +1,239 @@
+use glam::I64Vec2;
+use itertools::Itertools;
+
+use crate::{direction::Direction, math};
+
+/// A closed polygon through lattice points, the last corner joining back to the first, with
+/// the edges never crossing each other.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    vertices: Vec<I64Vec2>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<I64Vec2>) -> Polygon {
+        Polygon { vertices }
+    }
+
+    /// The corners met walking each step's distance in its direction, starting from the origin
+    /// and ending back there.
+    pub fn from_steps(steps: impl IntoIterator<Item = (Direction, i64)>) -> Polygon {
+        let vertices = steps
+            .into_iter()
+            .scan(I64Vec2::ZERO, |corner, (direction, distance)| {
+                *corner += direction.to_ivec2().as_i64vec2() * distance;
+                Some(*corner)
+            })
+            .collect();
+
+        Polygon { vertices }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (I64Vec2, I64Vec2)> + '_ {
+        self.vertices.iter().copied().circular_tuple_windows()
+    }
+
+    /// Twice the area, which is always a whole number for a lattice polygon, by the shoelace
+    /// formula.
+    pub fn double_area(&self) -> i128 {
+        self.edges()
+            .map(|(a, b)| a.x as i128 * b.y as i128 - b.x as i128 * a.y as i128)
+            .sum::<i128>()
+            .abs()
+    }
+
+    /// How many lattice points the edges pass through.
+    pub fn boundary_points(&self) -> i128 {
+        self.edges()
+            .map(|(a, b)| {
+                let step = (b - a).abs();
+                math::gcd(step.x as u64, step.y as u64) as i128
+            })
+            .sum()
+    }
+
+    /// How many lattice points are strictly inside, by Pick's theorem:
+    /// `area = interior + boundary / 2 - 1`.
+    pub fn interior_points(&self) -> i128 {
+        (self.double_area() - self.boundary_points()) / 2 + 1
+    }
+
+    /// How many lattice points are inside or on the edges, which is how many unit squares a
+    /// path of them along the edges would enclose, path included.
+    pub fn lattice_points(&self) -> i128 {
+        self.interior_points() + self.boundary_points()
+    }
+
+    pub fn on_boundary(&self, point: I64Vec2) -> bool {
+        self.edges().any(|(a, b)| {
+            cross(a, b, point) == 0
+                && (a.x.min(b.x)..=a.x.max(b.x)).contains(&point.x)
+                && (a.y.min(b.y)..=a.y.max(b.y)).contains(&point.y)
+        })
+    }
+
+    /// How many times the edges go round `point`, counting one way as positive and the other
+    /// as negative. Only meaningful for points off the boundary.
+    pub fn winding_number(&self, point: I64Vec2) -> i64 {
+        self.edges()
+            .map(|(a, b)| {
+                if a.y <= point.y && b.y > point.y && cross(a, b, point) > 0 {
+                    1
+                } else if a.y > point.y && b.y <= point.y && cross(a, b, point) < 0 {
+                    -1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// Whether `point` is strictly inside.
+    pub fn contains(&self, point: I64Vec2) -> bool {
+        !self.on_boundary(point) && self.winding_number(point) != 0
+    }
+}
+
+/// Which side of the line from `a` to `b` the point `p` is on, zero if it's on the line.
+fn cross(a: I64Vec2, b: I64Vec2, p: I64Vec2) -> i128 {
+    let (edge, to_point) = (b - a, p - a);
+    edge.x as i128 * to_point.y as i128 - edge.y as i128 * to_point.x as i128
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::i64vec2;
+
+    use super::*;
+    use crate::rng::{self, Rng};
+
+    const CASES: u64 = 1000;
+
+    #[test]
+    fn polygon_histogram_holds() {
+        rng::check_cases(CASES, check_histogram);
+    }
+
+    #[test]
+    fn polygon_triangle_holds() {
+        rng::check_cases(CASES, check_triangle);
+    }
+
+    #[test]
+    fn unit_square() {
+        let square = Polygon::new(vec![
+            i64vec2(0, 0),
+            i64vec2(1, 0),
+            i64vec2(1, 1),
+            i64vec2(0, 1),
+        ]);
+        assert_eq!(square.double_area(), 2);
+        assert_eq!(square.boundary_points(), 4);
+        assert_eq!(square.interior_points(), 0);
+        assert_eq!(square.lattice_points(), 4);
+        assert!(square.on_boundary(i64vec2(1, 1)));
+        assert!(!square.contains(i64vec2(1, 1)));
+        assert!(!square.contains(i64vec2(2, 0)));
+    }
+
+    #[test]
+    fn winding_either_way_round() {
+        let mut vertices = vec![i64vec2(0, 0), i64vec2(4, 0), i64vec2(4, 4), i64vec2(0, 4)];
+        let forwards = Polygon::new(vertices.clone());
+        vertices.reverse();
+        let backwards = Polygon::new(vertices);
+        let centre = i64vec2(2, 2);
+        assert_eq!(
+            forwards.winding_number(centre),
+            -backwards.winding_number(centre)
+        );
+        assert_ne!(forwards.winding_number(centre), 0);
+        assert_eq!(forwards.winding_number(i64vec2(5, 2)), 0);
+        // level with a corner, where a ray could count the edges meeting there twice
+        assert_eq!(forwards.winding_number(i64vec2(-1, 0)), 0);
+        assert_eq!(forwards.winding_number(i64vec2(-1, 4)), 0);
+        assert_eq!(forwards.interior_points(), backwards.interior_points());
+    }
+
+    #[test]
+    fn from_steps_round_a_square() {
+        let polygon = Polygon::from_steps([
+            (Direction::E, 3),
+            (Direction::S, 3),
+            (Direction::W, 3),
+            (Direction::N, 3),
+        ]);
+        assert_eq!(polygon.boundary_points(), 12);
+        assert_eq!(polygon.interior_points(), 4);
+        assert_eq!(polygon.lattice_points(), 16);
+    }
+
+    #[test]
+    fn no_area() {
+        // there and back along a line, the outline of nothing
+        let line = Polygon::new(vec![i64vec2(0, 0), i64vec2(3, 0)]);
+        assert_eq!(line.double_area(), 0);
+        assert_eq!(line.boundary_points(), 6);
+        assert!(line.on_boundary(i64vec2(2, 0)));
+        assert!(!line.contains(i64vec2(2, 0)));
+        assert!(!line.contains(i64vec2(2, 1)));
+
+        let empty = Polygon::new(vec![]);
+        assert_eq!(empty.double_area(), 0);
+        assert_eq!(empty.boundary_points(), 0);
+        assert!(!empty.contains(I64Vec2::ZERO));
+    }
+
+    /// Columns of random heights standing on the x axis, the outline going either way round.
+    fn check_histogram(rng: &mut Rng) -> Result<(), String> {
+        let mut vertices = vec![i64vec2(0, 0)];
+        let mut x = 0;
+        for _ in 0..rng.range(1..6) {
+            let height = rng.range(1..8);
+            vertices.push(i64vec2(x, height));
+            x += rng.range(1..5);
+            vertices.push(i64vec2(x, height));
+        }
+        vertices.push(i64vec2(x, 0));
+        if rng.chance(0.5) {
+            vertices.reverse();
+        }
+
+        check_counts(&Polygon::new(vertices))
+    }
+
+    /// Edges at any angle, crossing lattice points along the way or not.
+    fn check_triangle(rng: &mut Rng) -> Result<(), String> {
+        let vertices: Vec<_> = (0..3)
+            .map(|_| i64vec2(rng.range(-6..7), rng.range(-6..7)))
+            .collect();
+        let polygon = Polygon::new(vertices);
+        if polygon.double_area() == 0 {
+            return Ok(());
+        }
+
+        check_counts(&polygon)
+    }
+
+    fn check_counts(polygon: &Polygon) -> Result<(), String> {
+        let min = polygon.vertices.iter().fold(I64Vec2::MAX, |m, v| m.min(*v));
+        let max = polygon.vertices.iter().fold(I64Vec2::MIN, |m, v| m.max(*v));
+        let points: Vec<_> = (min.x - 1..=max.x + 1)
+            .cartesian_product(min.y - 1..=max.y + 1)
+            .map(|(x, y)| i64vec2(x, y))
+            .collect();
+
+        let boundary = points.iter().filter(|p| polygon.on_boundary(**p)).count() as i128;
+        let interior = points.iter().filter(|p| polygon.contains(**p)).count() as i128;
+        if (polygon.boundary_points(), polygon.interior_points()) != (boundary, interior) {
+            return Err(format!(
+                "{:?} has {} boundary and {} interior points, not {boundary} and {interior}",
+                polygon.vertices,
+                polygon.boundary_points(),
+                polygon.interior_points()
+            ));
+        }
+
+        Ok(())
+    }
+}
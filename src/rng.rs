@@ -0,0 +1,58 @@
+use std::ops::Range;
+
+/// SplitMix64. Small, fast and, unlike a crate RNG, guaranteed to give the same stream for
+/// a seed forever, which is what makes generated inputs reproducible.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform value in `range`, which must not be empty.
+    pub fn range(&mut self, range: Range<i64>) -> i64 {
+        assert!(!range.is_empty(), "empty range {range:?}");
+        let len = range.end.abs_diff(range.start);
+        range.start.wrapping_add((self.next_u64() % len) as i64)
+    }
+
+    /// A uniform value in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.range(0..n as i64) as usize
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        unit < probability
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Runs `property` on `cases` random inputs, each from an `Rng` seeded with its case number,
+/// and fails on the first one that breaks it, naming the seed to replay it from.
+#[cfg(test)]
+pub fn check_cases(cases: u64, property: impl Fn(&mut Rng) -> Result<(), String>) {
+    for seed in 0..cases {
+        if let Err(reason) = property(&mut Rng::new(seed)) {
+            panic!("fails at seed {seed}: {reason}");
+        }
+    }
+}
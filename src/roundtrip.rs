@@ -0,0 +1,115 @@
+use std::panic::{self, AssertUnwindSafe, catch_unwind};
+
+use crate::{config::Config, days, load_input, rng::Rng};
+
+/// Prints and reparses each day's model for its real input, if there is one, and for
+/// `cases` random inputs, reporting the first one that doesn't come back the same.
+pub fn roundtrip(only_day: Option<u8>, cases: u64, seed: u64, config: &Config) {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    for (day, entry) in days::all() {
+        let Some(round_trip) = entry.round_trip else {
+            continue;
+        };
+        if only_day.is_some_and(|only_day| only_day != day) {
+            continue;
+        }
+
+        let real = load_input(day, config)
+            .ok()
+            .map(|input| ("real input".to_string(), input));
+        let generated = (0..cases).map(|case| {
+            let case_seed = seed.wrapping_add(case);
+            let size = 1 + (case % 4) as usize;
+            let input = (entry.generate)(&mut Rng::new(case_seed), size);
+            (format!("seed {case_seed}, size {size}"), input)
+        });
+
+        let mut checked = 0;
+        let failure = real.into_iter().chain(generated).find_map(|(name, input)| {
+            checked += 1;
+            match catch_unwind(AssertUnwindSafe(|| round_trip(&input))) {
+                Ok(Ok(())) => None,
+                Ok(Err(printed)) => Some((name, input, Some(printed))),
+                Err(_) => Some((name, input, None)),
+            }
+        });
+
+        match failure {
+            None => println!("day {day}: {checked} inputs round-trip"),
+            Some((name, input, printed)) => {
+                println!("day {day}: round trip failed for {name}");
+                println!("{input}\n");
+                match printed {
+                    Some(printed) => println!("printed as:\n{printed}\n"),
+                    None => println!("which doesn't parse\n"),
+                }
+            }
+        }
+    }
+
+    panic::set_hook(previous_hook);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{days, rng::Rng};
+
+    /// Round-trips the day's generated inputs for the first few seeds at each of the sizes
+    /// `roundtrip` itself cycles through.
+    fn check(day: u8) {
+        let entry = days::get(day).expect("day not implemented");
+        let round_trip = entry.round_trip.expect("day has no round trip");
+        for seed in 0..5 {
+            for size in 1..=4 {
+                let input = (entry.generate)(&mut Rng::new(seed), size);
+                if let Err(printed) = round_trip(&input) {
+                    panic!("seed {seed}, size {size}:\n{input}\n\nprinted as:\n{printed}");
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "day2")]
+    #[test]
+    fn day2_round_trips() {
+        check(2);
+    }
+
+    #[cfg(feature = "day4")]
+    #[test]
+    fn day4_round_trips() {
+        check(4);
+    }
+
+    #[cfg(feature = "day5")]
+    #[test]
+    fn day5_round_trips() {
+        check(5);
+    }
+
+    #[cfg(feature = "day7")]
+    #[test]
+    fn day7_round_trips() {
+        check(7);
+    }
+
+    #[cfg(feature = "day8")]
+    #[test]
+    fn day8_round_trips() {
+        check(8);
+    }
+
+    #[cfg(feature = "day12")]
+    #[test]
+    fn day12_round_trips() {
+        check(12);
+    }
+
+    #[cfg(feature = "day19")]
+    #[test]
+    fn day19_round_trips() {
+        check(19);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    panic::{self, AssertUnwindSafe, catch_unwind},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::{Config, Format},
+    days::{Day, Solver},
+    rng::Rng,
+};
+
+/// Each size is timed this many times, on different inputs, and the median kept.
+const RUNS: u64 = 3;
+/// Times below this are mostly noise and fixed costs, so they're left out of the fit when
+/// there's enough else.
+const NOISE_FLOOR: Duration = Duration::from_millis(1);
+
+struct Sample {
+    size: usize,
+    /// How big the input really was along the axis being grown.
+    amount: usize,
+    time: Duration,
+}
+
+/// Times `solve` on ever larger inputs, doubling the size until one takes longer than
+/// `budget` or `max_size` is reached, and fits `time ~ amount^k` to the results.
+pub fn scale(
+    day: u8,
+    entry: &Day,
+    solve: Solver,
+    config: &Config,
+    budget: Duration,
+    max_size: usize,
+    seed: u64,
+) {
+    // days that don't scale along their generator's size have their own way of growing, unless
+    // a particular implementation was asked for
+    let scaler = entry.scale.filter(|_| config.implementation.is_none());
+    let axis = if scaler.is_some() {
+        "times unfolded"
+    } else {
+        "input bytes"
+    };
+
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    // one untimed run first, so the first size doesn't pay for warming up caches and allocators
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        solve(&(entry.generate)(&mut Rng::new(seed), 1))
+    }));
+    let mut samples = vec![];
+    let mut size = 1;
+    while size <= max_size {
+        let mut runs: Vec<(usize, Duration)> = (0..RUNS)
+            .map_while(|run| {
+                catch_unwind(AssertUnwindSafe(|| match scaler {
+                    Some(scaler) => {
+                        let input = (entry.generate)(&mut Rng::new(seed), 4);
+                        let start = Instant::now();
+                        scaler(&input, size);
+                        (size, start.elapsed())
+                    }
+                    None => {
+                        let input = (entry.generate)(&mut Rng::new(seed.wrapping_add(run)), size);
+                        let start = Instant::now();
+                        solve(&input);
+                        (input.len(), start.elapsed())
+                    }
+                }))
+                .ok()
+            })
+            .collect();
+        if runs.len() < RUNS as usize {
+            println!("day {day} panicked at size {size}, stopping there");
+            break;
+        }
+
+        runs.sort_by_key(|(_, time)| *time);
+        let (amount, time) = runs[runs.len() / 2];
+        samples.push(Sample { size, amount, time });
+        if time > budget {
+            break;
+        }
+        size *= 2;
+    }
+    panic::set_hook(previous_hook);
+
+    let exponent = fit(&samples);
+    match config.format {
+        Format::Text => {
+            println!("day {day}, growing {axis}, median of {RUNS} runs per size");
+            println!(
+                "{:>6}  {:>10}  {:>12}  {:>8}  {:>8}  {:>6}",
+                "size", "amount", "time", "ln n", "ln µs", "slope"
+            );
+            for (i, sample) in samples.iter().enumerate() {
+                let (ln_n, ln_t) = logs(sample);
+                let slope = match i.checked_sub(1).map(|j| logs(&samples[j])) {
+                    Some((prev_n, prev_t)) if ln_n > prev_n => {
+                        format!("{:.2}", (ln_t - prev_t) / (ln_n - prev_n))
+                    }
+                    _ => "-".to_string(),
+                };
+                println!(
+                    "{:>6}  {:>10}  {:>12}  {ln_n:>8.2}  {ln_t:>8.2}  {slope:>6}",
+                    sample.size,
+                    sample.amount,
+                    format!("{:.2?}", sample.time)
+                );
+            }
+            match exponent {
+                Some(exponent) => println!(
+                    "estimated exponent {exponent:.2}, so roughly O(n^{}) in {axis}",
+                    (exponent * 2.0).round() / 2.0
+                ),
+                None => println!("not enough sizes to estimate an exponent"),
+            }
+        }
+        Format::Json => {
+            let samples: Vec<_> = samples
+                .iter()
+                .map(|sample| {
+                    serde_json::json!({
+                        "size": sample.size,
+                        "amount": sample.amount,
+                        "ns": sample.time.as_nanos() as u64,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::json!({ "day": day, "axis": axis, "samples": samples, "exponent": exponent })
+            );
+        }
+    }
+}
+
+fn logs(sample: &Sample) -> (f64, f64) {
+    (
+        (sample.amount as f64).ln(),
+        (sample.time.as_secs_f64() * 1e6).ln(),
+    )
+}
+
+/// Least squares slope of ln time against ln amount.
+fn fit(samples: &[Sample]) -> Option<f64> {
+    let above_noise: Vec<_> = samples
+        .iter()
+        .filter(|sample| sample.time >= NOISE_FLOOR)
+        .collect();
+    let points: Vec<_> = if above_noise.len() >= 3 {
+        above_noise.into_iter().map(logs).collect()
+    } else {
+        samples.iter().map(logs).collect()
+    };
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let spread: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if points.len() < 2 || spread == 0.0 {
+        return None;
+    }
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+
+    Some(covariance / spread)
+}
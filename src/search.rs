@@ -0,0 +1,501 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque, hash_map::Entry},
+    hash::Hash,
+};
+
+/// A place a search can be, in a `World` that says where it can go from there.
+pub trait SearchState<W: ?Sized>: Clone + Eq + Hash {
+    /// The states one move away, each with what that move costs.
+    fn successors(&self, world: &W) -> Vec<(Self, u64)>;
+
+    /// Never more than the cheapest cost from here to a goal, and never dropping by more
+    /// than a move costs along the way, or A* can settle on a worse path.
+    fn heuristic(&self, _world: &W) -> u64 {
+        0
+    }
+
+    /// Where the search stops. Searches after the distance to everything reachable can
+    /// leave this alone.
+    fn is_goal(&self, _world: &W) -> bool {
+        false
+    }
+}
+
+/// The cheapest way from any of `starts` to a goal, trying states in order of cost so far.
+pub fn dijkstra<W: ?Sized, S: SearchState<W>>(
+    starts: impl IntoIterator<Item = S>,
+    world: &W,
+) -> Outcome<S> {
+    search(starts, world, Heap::default(), false)
+}
+
+/// The same as `dijkstra`, but trying states in order of cost so far plus the heuristic, so
+/// it heads for the goal first.
+pub fn astar<W: ?Sized, S: SearchState<W>>(
+    starts: impl IntoIterator<Item = S>,
+    world: &W,
+) -> Outcome<S> {
+    search(starts, world, Heap::default(), true)
+}
+
+/// The fewest moves from any of `starts` to a goal, every move costing one whatever the
+/// state says.
+pub fn bfs<W: ?Sized, S: SearchState<W>>(
+    starts: impl IntoIterator<Item = S>,
+    world: &W,
+) -> Outcome<S> {
+    let unit = |state: &S| {
+        state
+            .successors(world)
+            .into_iter()
+            .map(|(next, _)| (next, 1))
+            .collect()
+    };
+    run(starts, world, unit, VecDeque::new(), false)
+}
+
+/// The same as `dijkstra`, keeping a list of states for every cost instead of a heap,
+/// which is quicker when moves are cheap and a path never costs much.
+pub fn bucket<W: ?Sized, S: SearchState<W>>(
+    starts: impl IntoIterator<Item = S>,
+    world: &W,
+) -> Outcome<S> {
+    search(starts, world, Buckets::default(), false)
+}
+
+/// How a search went: everything it reached on the way, and the goal if it found one.
+pub struct Outcome<S> {
+    /// The cheapest cost found to each state, the state it was reached from and whether it's
+    /// been expanded, at which point that cost is final.
+    reached: HashMap<S, (u64, Option<S>, bool)>,
+    goal: Option<S>,
+    expanded: usize,
+}
+
+impl<S: Clone + Eq + Hash> Outcome<S> {
+    /// What the cheapest path to the goal costs.
+    pub fn cost(&self) -> Option<u64> {
+        self.goal.as_ref().map(|goal| self.reached[goal].0)
+    }
+
+    /// The cheapest path to the goal, from the start it set off from to the goal itself.
+    pub fn path(&self) -> Option<Vec<S>> {
+        let mut path = vec![self.goal.clone()?];
+        while let Some(previous) = &self.reached[path.last().unwrap()].1 {
+            path.push(previous.clone());
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// How many states the search took its next steps from.
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    /// Every state expanded along with the cheapest cost to it, which is everything
+    /// reachable when there's no goal.
+    pub fn costs(&self) -> impl Iterator<Item = (&S, u64)> {
+        self.reached
+            .iter()
+            .filter(|(_, (_, _, expanded))| *expanded)
+            .map(|(state, (cost, _, _))| (state, *cost))
+    }
+}
+
+/// The states waiting to be expanded, each with the priority it was pushed at.
+trait Frontier<S> {
+    fn push(&mut self, priority: u64, state: S);
+    fn pop(&mut self) -> Option<S>;
+}
+
+/// A binary heap of indices into the states pushed, so the states don't need an order.
+struct Heap<S> {
+    queue: BinaryHeap<Reverse<(u64, usize)>>,
+    states: Vec<Option<S>>,
+}
+
+impl<S> Default for Heap<S> {
+    fn default() -> Heap<S> {
+        Heap {
+            queue: BinaryHeap::new(),
+            states: vec![],
+        }
+    }
+}
+
+impl<S> Frontier<S> for Heap<S> {
+    fn push(&mut self, priority: u64, state: S) {
+        self.queue.push(Reverse((priority, self.states.len())));
+        self.states.push(Some(state));
+    }
+
+    fn pop(&mut self) -> Option<S> {
+        let Reverse((_, i)) = self.queue.pop()?;
+        self.states[i].take()
+    }
+}
+
+/// First in, first out, which is cheapest first when every move costs the same.
+impl<S> Frontier<S> for VecDeque<S> {
+    fn push(&mut self, _priority: u64, state: S) {
+        self.push_back(state);
+    }
+
+    fn pop(&mut self) -> Option<S> {
+        self.pop_front()
+    }
+}
+
+/// A list of states for each priority, relying on nothing ever being pushed below the one
+/// last popped.
+struct Buckets<S> {
+    buckets: Vec<Vec<S>>,
+    current: usize,
+}
+
+impl<S> Default for Buckets<S> {
+    fn default() -> Buckets<S> {
+        Buckets {
+            buckets: vec![],
+            current: 0,
+        }
+    }
+}
+
+impl<S> Frontier<S> for Buckets<S> {
+    fn push(&mut self, priority: u64, state: S) {
+        let priority = priority as usize;
+        assert!(priority >= self.current, "a bucket queue can't go back");
+        if priority >= self.buckets.len() {
+            self.buckets.resize_with(priority + 1, Vec::new);
+        }
+        self.buckets[priority].push(state);
+    }
+
+    fn pop(&mut self) -> Option<S> {
+        while self.current < self.buckets.len() {
+            if let Some(state) = self.buckets[self.current].pop() {
+                return Some(state);
+            }
+            self.current += 1;
+        }
+
+        None
+    }
+}
+
+fn search<W: ?Sized, S: SearchState<W>>(
+    starts: impl IntoIterator<Item = S>,
+    world: &W,
+    frontier: impl Frontier<S>,
+    use_heuristic: bool,
+) -> Outcome<S> {
+    run(
+        starts,
+        world,
+        |state: &S| state.successors(world),
+        frontier,
+        use_heuristic,
+    )
+}
+
+/// States are only final once they're popped, so a state pushed again at a lower cost just
+/// leaves its old entry to be skipped when it comes up.
+fn run<W: ?Sized, S: SearchState<W>>(
+    starts: impl IntoIterator<Item = S>,
+    world: &W,
+    successors: impl Fn(&S) -> Vec<(S, u64)>,
+    mut frontier: impl Frontier<S>,
+    use_heuristic: bool,
+) -> Outcome<S> {
+    let priority = |state: &S, cost: u64| {
+        if use_heuristic {
+            cost + state.heuristic(world)
+        } else {
+            cost
+        }
+    };
+
+    let mut outcome = Outcome {
+        reached: HashMap::new(),
+        goal: None,
+        expanded: 0,
+    };
+    for start in starts {
+        if let Entry::Vacant(entry) = outcome.reached.entry(start.clone()) {
+            entry.insert((0, None, false));
+            frontier.push(priority(&start, 0), start);
+        }
+    }
+
+    while let Some(state) = frontier.pop() {
+        let (cost, _, expanded) = outcome.reached.get_mut(&state).unwrap();
+        if *expanded {
+            continue;
+        }
+        *expanded = true;
+        let cost = *cost;
+        outcome.expanded += 1;
+        if state.is_goal(world) {
+            outcome.goal = Some(state);
+            break;
+        }
+
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+            match outcome.reached.entry(next.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert((next_cost, Some(state.clone()), false));
+                }
+                Entry::Occupied(mut entry) => {
+                    let (best, _, expanded) = entry.get();
+                    if *expanded || *best <= next_cost {
+                        continue;
+                    }
+                    entry.insert((next_cost, Some(state.clone()), false));
+                }
+            }
+            frontier.push(priority(&next, next_cost), next);
+        }
+    }
+
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{self, Rng};
+
+    const CASES: u64 = 1000;
+
+    #[test]
+    fn dijkstra_holds() {
+        rng::check_cases(CASES, check_dijkstra);
+    }
+
+    #[test]
+    fn astar_holds() {
+        rng::check_cases(CASES, check_astar);
+    }
+
+    #[test]
+    fn bfs_holds() {
+        rng::check_cases(CASES, check_bfs);
+    }
+
+    #[test]
+    fn bucket_holds() {
+        rng::check_cases(CASES, check_bucket);
+    }
+
+    fn graph(edges: Vec<Vec<(usize, u64)>>, goal: usize) -> Graph {
+        let heuristic = vec![0; edges.len()];
+        Graph {
+            edges,
+            goal,
+            heuristic,
+        }
+    }
+
+    #[test]
+    fn start_is_the_goal() {
+        let graph = graph(vec![vec![(0, 5), (1, 1)], vec![]], 0);
+        for outcome in [
+            dijkstra([0], &graph),
+            astar([0], &graph),
+            bfs([0], &graph),
+            bucket([0], &graph),
+        ] {
+            assert_eq!(outcome.cost(), Some(0));
+            assert_eq!(outcome.path(), Some(vec![0]));
+            assert_eq!(outcome.expanded(), 1);
+        }
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        // a self loop and a cycle, neither of which gets anywhere near node 3
+        let graph = graph(
+            vec![vec![(0, 1), (1, 2)], vec![(2, 0)], vec![(1, 4)], vec![]],
+            3,
+        );
+        for outcome in [
+            dijkstra([0], &graph),
+            astar([0], &graph),
+            bfs([0], &graph),
+            bucket([0], &graph),
+        ] {
+            assert_eq!(outcome.cost(), None);
+            assert_eq!(outcome.path(), None);
+            assert_eq!(outcome.costs().count(), 3);
+        }
+        let mut costs: Vec<_> = dijkstra([0], &graph)
+            .costs()
+            .map(|(&node, cost)| (node, cost))
+            .collect();
+        costs.sort();
+        assert_eq!(costs, [(0, 0), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn free_moves() {
+        // zero cost edges have to be settled before anything at the same cost is
+        let graph = graph(
+            vec![vec![(1, 0), (2, 1)], vec![(2, 0)], vec![(3, 0)], vec![]],
+            3,
+        );
+        for outcome in [dijkstra([0], &graph), bucket([0], &graph)] {
+            assert_eq!(outcome.cost(), Some(0));
+            assert_eq!(outcome.path(), Some(vec![0, 1, 2, 3]));
+        }
+    }
+
+    #[test]
+    fn several_starts() {
+        let graph = graph(vec![vec![(2, 5)], vec![(2, 1)], vec![]], 2);
+        for outcome in [dijkstra([0, 1, 1], &graph), bucket([1, 0], &graph)] {
+            assert_eq!(outcome.cost(), Some(1));
+            assert_eq!(outcome.path(), Some(vec![1, 2]));
+        }
+        assert_eq!(dijkstra(Vec::<usize>::new(), &graph).cost(), None);
+    }
+
+    /// Numbered nodes with weighted one way edges, to search from node 0 to `goal`.
+    struct Graph {
+        edges: Vec<Vec<(usize, u64)>>,
+        goal: usize,
+        heuristic: Vec<u64>,
+    }
+
+    impl SearchState<Graph> for usize {
+        fn successors(&self, graph: &Graph) -> Vec<(usize, u64)> {
+            graph.edges[*self].clone()
+        }
+
+        fn heuristic(&self, graph: &Graph) -> u64 {
+            graph.heuristic[*self]
+        }
+
+        fn is_goal(&self, graph: &Graph) -> bool {
+            *self == graph.goal
+        }
+    }
+
+    fn random_graph(rng: &mut Rng, max_cost: u64) -> Graph {
+        let n = rng.range(1..12) as usize;
+        let edges = (0..n)
+            .map(|_| {
+                (0..rng.below(4))
+                    .map(|_| (rng.below(n), rng.below(max_cost as usize + 1) as u64))
+                    .collect()
+            })
+            .collect();
+
+        Graph {
+            edges,
+            goal: rng.below(n),
+            heuristic: vec![0; n],
+        }
+    }
+
+    /// The cheapest cost from each node to the goal, by relaxing every edge until nothing
+    /// changes.
+    fn costs_to_goal(graph: &Graph) -> Vec<Option<u64>> {
+        let mut costs = vec![None; graph.edges.len()];
+        costs[graph.goal] = Some(0);
+        for _ in 0..graph.edges.len() {
+            for (from, edges) in graph.edges.iter().enumerate() {
+                for &(to, cost) in edges {
+                    if let Some(rest) = costs[to]
+                        && costs[from].is_none_or(|best| rest + cost < best)
+                    {
+                        costs[from] = Some(rest + cost);
+                    }
+                }
+            }
+        }
+
+        costs
+    }
+
+    fn check_search(graph: &Graph, outcome: Outcome<usize>) -> Result<(), String> {
+        let expected = costs_to_goal(graph)[0];
+        if outcome.cost() != expected {
+            return Err(format!(
+                "{:?} to {} costs {:?}, not {expected:?}",
+                graph.edges,
+                graph.goal,
+                outcome.cost()
+            ));
+        }
+
+        if let Some(path) = outcome.path() {
+            let moves: Option<Vec<u64>> = path
+                .windows(2)
+                .map(|pair| {
+                    graph.edges[pair[0]]
+                        .iter()
+                        .filter(|(to, _)| *to == pair[1])
+                        .map(|&(_, cost)| cost)
+                        .min()
+                })
+                .collect();
+            if path[0] != 0
+                || *path.last().unwrap() != graph.goal
+                || moves.map(|moves| moves.iter().sum()) != expected
+            {
+                return Err(format!(
+                    "{:?} to {} goes by {path:?}",
+                    graph.edges, graph.goal
+                ));
+            }
+            // every state on the path was expanded, and none more than once
+            if outcome.expanded() < path.len() || outcome.expanded() > graph.edges.len() {
+                return Err(format!(
+                    "{:?} to {} expanded {} states",
+                    graph.edges,
+                    graph.goal,
+                    outcome.expanded()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_dijkstra(rng: &mut Rng) -> Result<(), String> {
+        let graph = random_graph(rng, 9);
+        check_search(&graph, dijkstra([0], &graph))
+    }
+
+    fn check_astar(rng: &mut Rng) -> Result<(), String> {
+        let mut graph = random_graph(rng, 9);
+        // half the true cost to the goal never overestimates and never drops faster than a move
+        graph.heuristic = costs_to_goal(&graph)
+            .into_iter()
+            .map(|cost| cost.map_or(0, |cost| cost / 2))
+            .collect();
+        check_search(&graph, astar([0], &graph))
+    }
+
+    fn check_bfs(rng: &mut Rng) -> Result<(), String> {
+        let mut graph = random_graph(rng, 9);
+        let outcome = bfs([0], &graph);
+        // the answer it should have found ignoring the costs
+        for edges in &mut graph.edges {
+            for (_, cost) in edges {
+                *cost = 1;
+            }
+        }
+        check_search(&graph, outcome)
+    }
+
+    fn check_bucket(rng: &mut Rng) -> Result<(), String> {
+        let graph = random_graph(rng, 3);
+        check_search(&graph, bucket([0], &graph))
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    fs::{read_dir, read_to_string},
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{Solver, config::Config, solve_with_timeout};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Re-solves `day` every time one of its input files changes, until interrupted.
+pub fn watch_day(day: u8, func: Solver, config: &Config) {
+    let mut seen: HashMap<PathBuf, SystemTime> = HashMap::new();
+    let mut previous: HashMap<PathBuf, (String, String)> = HashMap::new();
+
+    let timeout = config.day(day).timeout();
+
+    println!(
+        "watching {} for day {day} inputs, ctrl-c to stop",
+        config.input_dir.display()
+    );
+    loop {
+        for path in watched_files(day, config) {
+            let Ok(modified) = path.metadata().and_then(|meta| meta.modified()) else {
+                continue;
+            };
+            if seen.insert(path.clone(), modified) == Some(modified) {
+                continue;
+            }
+            let Ok(input) = read_to_string(&path) else {
+                continue;
+            };
+
+            let name = path.file_name().unwrap().to_string_lossy();
+            let start = Instant::now();
+            // a half-edited input shouldn't kill the watcher, the panic message is enough, and
+            // one that sends the solver off forever shouldn't stop it watching either
+            let solution = catch_unwind(AssertUnwindSafe(|| {
+                solve_with_timeout(move || func(&input), timeout)
+            }));
+            let (p1, p2) = match solution {
+                Ok(Some(solution)) => solution,
+                Ok(None) => {
+                    println!("[{name}] timed out after {:?}\n", timeout.unwrap());
+                    continue;
+                }
+                Err(_) => {
+                    println!("[{name}] solver panicked\n");
+                    continue;
+                }
+            };
+            println!("[{name}] solved in {:?}", start.elapsed());
+
+            let old = previous.get(&path);
+            println!(
+                "Solution 1: {p1}{}",
+                describe_change(old.map(|old| &old.0), &p1)
+            );
+            println!(
+                "Solution 2: {p2}{}",
+                describe_change(old.map(|old| &old.1), &p2)
+            );
+            println!();
+
+            previous.insert(path, (p1, p2));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The day's real input plus any hand-made variants next to it, e.g. `input10-small.txt`
+/// or `example10.txt`.
+fn watched_files(day: u8, config: &Config) -> Vec<PathBuf> {
+    let Ok(entries) = read_dir(&config.input_dir) else {
+        return vec![];
+    };
+    let prefixes = [format!("input{day}"), format!("example{day}")];
+
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                return false;
+            };
+            prefixes.iter().any(|prefix| {
+                name.strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| !rest.starts_with(|ch: char| ch.is_ascii_digit()))
+            })
+        })
+        .collect();
+    files.sort();
+
+    files
+}
+
+fn describe_change(old: Option<&String>, new: &str) -> String {
+    match old {
+        None => "".to_string(),
+        Some(old) if old == new => " (unchanged)".to_string(),
+        Some(old) => format!(" (was {old})"),
+    }
+}